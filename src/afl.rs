@@ -0,0 +1,213 @@
+use crate::{Args, JobStatus, report_jobs};
+use fuzz_exporter::Parsed;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// Polls the `fuzzer_stats` files of every AFL++ instance in `out_dir`.
+///
+/// AFL++ rewrites them in place rather than appending, so they're re-read
+/// on every tick instead of tailed.
+pub async fn afl_parser(args: &Args, out_dir: &Path) -> anyhow::Result<()> {
+    tracing::info!("starting AFL++ parser");
+
+    let mut instances: HashMap<PathBuf, JobStatus> = HashMap::new();
+    let retired = JobStatus::default();
+    let mut report = tokio::time::interval(Duration::from_secs(args.interval_secs));
+    loop {
+        report.tick().await;
+
+        let stats_files = find_stats(out_dir)?;
+        instances.retain(|path, instance| {
+            let exists = stats_files.contains(path);
+            if !exists {
+                crate::set_job_removed(&instance.name, true);
+                retired.retire(instance);
+            }
+            exists
+        });
+        for path in stats_files {
+            let Ok(stats) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+            let stats = AflStats::parse(&stats);
+            let instance = instances.entry(path.clone()).or_insert_with(|| {
+                let name = instance_name(out_dir, &path);
+                crate::set_job_removed(&name, false);
+                metrics::counter!("jobs_total").increment(1);
+                JobStatus {
+                    name,
+                    added_at: crate::unix_secs(),
+                    ..Default::default()
+                }
+            });
+            stats.update(instance);
+        }
+
+        let statuses: Vec<_> = instances.values().collect();
+        report_jobs(&statuses, &retired, args);
+    }
+}
+
+/// `out/fuzzer_stats` for a single instance, `out/<name>/fuzzer_stats` with `-M`/`-S`.
+fn find_stats(out_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut stats = Vec::new();
+    let single = out_dir.join("fuzzer_stats");
+    if single.is_file() {
+        stats.push(single);
+    }
+    for entry in std::fs::read_dir(out_dir)? {
+        let path = entry?.path().join("fuzzer_stats");
+        if path.is_file() {
+            stats.push(path);
+        }
+    }
+    Ok(stats)
+}
+
+fn instance_name(out_dir: &Path, stats: &Path) -> String {
+    stats
+        .parent()
+        .filter(|dir| *dir != out_dir)
+        .and_then(|dir| dir.file_name())
+        .map_or_else(
+            || "default".to_owned(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+}
+
+// start_time        : 1700000000
+// last_update       : 1700003600
+// execs_done        : 123456789
+// execs_per_sec     : 3429.05
+// corpus_count      : 1520
+// saved_crashes     : 2
+// saved_hangs       : 0
+// bitmap_cvg        : 12.34%
+#[derive(Debug, Default, PartialEq)]
+struct AflStats {
+    last_update: u64,
+    execs_done: u64,
+    execs_per_sec: f64,
+    corpus_count: u32,
+    saved_crashes: u32,
+    saved_hangs: u32,
+    edges_found: u32,
+    bitmap_cvg: f64,
+    stability: f64,
+    cycles_done: u64,
+    pending_favs: u64,
+    pending_total: u64,
+}
+
+impl AflStats {
+    fn parse(stats: &str) -> AflStats {
+        let mut parsed = AflStats::default();
+        for line in stats.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().trim_end_matches('%');
+            fn num<T: std::str::FromStr + Default>(value: &str) -> T {
+                value.parse().unwrap_or_default()
+            }
+            match key.trim() {
+                "last_update" => parsed.last_update = num(value),
+                "execs_done" => parsed.execs_done = num(value),
+                "execs_per_sec" => parsed.execs_per_sec = num(value),
+                "corpus_count" => parsed.corpus_count = num(value),
+                "saved_crashes" => parsed.saved_crashes = num(value),
+                "saved_hangs" => parsed.saved_hangs = num(value),
+                "edges_found" => parsed.edges_found = num(value),
+                "bitmap_cvg" => parsed.bitmap_cvg = num(value),
+                "stability" => parsed.stability = num(value),
+                "cycles_done" => parsed.cycles_done = num(value),
+                "pending_favs" => parsed.pending_favs = num(value),
+                "pending_total" => parsed.pending_total = num(value),
+                _ => {}
+            }
+        }
+        parsed
+    }
+
+    /// Fields shared with libFuzzer go through `JobStatus` and the usual
+    /// aggregation, the AFL++-specific ones are published per instance.
+    fn update(&self, instance: &JobStatus) {
+        // not rewritten since the last read
+        if self.last_update != 0 && instance.last_update.load(Ordering::Relaxed) == self.last_update
+        {
+            return;
+        }
+
+        instance.update(&Parsed {
+            cov: self.edges_found,
+            corp: self.corpus_count,
+            exec_s: self.execs_per_sec.round() as u32,
+            total_execs: self.execs_done,
+            ..Default::default()
+        });
+        instance.crash.store(self.saved_crashes, Ordering::Relaxed);
+        instance.timeout.store(self.saved_hangs, Ordering::Relaxed);
+        // staleness is about AFL++ writing the file, not us reading it
+        if self.last_update != 0 {
+            instance
+                .last_update
+                .store(self.last_update, Ordering::Relaxed);
+        }
+
+        let job = instance.name.clone();
+        metrics::gauge!("afl_bitmap_cvg", "job" => job.clone()).set(self.bitmap_cvg);
+        metrics::gauge!("afl_stability", "job" => job.clone()).set(self.stability);
+        metrics::gauge!("afl_cycles_done", "job" => job.clone()).set(self.cycles_done as f64);
+        metrics::gauge!("afl_pending_favs", "job" => job.clone()).set(self.pending_favs as f64);
+        metrics::gauge!("afl_pending_total", "job" => job.clone()).set(self.pending_total as f64);
+        metrics::counter!("afl_execs_done", "job" => job).absolute(self.execs_done);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AflStats;
+
+    #[test]
+    fn test_parse_fuzzer_stats() {
+        let stats = "\
+start_time        : 1700000000
+last_update       : 1700003600
+run_time          : 3600
+fuzzer_pid        : 4242
+cycles_done       : 3
+execs_done        : 123456789
+execs_per_sec     : 3429.05
+corpus_count      : 1520
+corpus_favored    : 210
+pending_favs      : 12
+pending_total     : 800
+stability         : 99.87%
+bitmap_cvg        : 12.34%
+saved_crashes     : 2
+saved_hangs       : 1
+edges_found       : 8088
+target_mode       : shmem_testcase default
+command_line      : afl-fuzz -i in -o out -- ./target @@
+";
+        assert_eq!(
+            AflStats::parse(stats),
+            AflStats {
+                last_update: 1700003600,
+                execs_done: 123456789,
+                execs_per_sec: 3429.05,
+                corpus_count: 1520,
+                saved_crashes: 2,
+                saved_hangs: 1,
+                edges_found: 8088,
+                bitmap_cvg: 12.34,
+                stability: 99.87,
+                cycles_done: 3,
+                pending_favs: 12,
+                pending_total: 800,
+            }
+        );
+    }
+}
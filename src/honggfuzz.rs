@@ -0,0 +1,163 @@
+use crate::{Parsed, StatKind};
+use winnow::Result;
+use winnow::ascii::{dec_uint, digit1, space0};
+use winnow::combinator::{alt, preceded};
+use winnow::error::{ContextError, ParseError};
+use winnow::token::{rest, take_until};
+
+use winnow::prelude::*;
+
+/// honggfuzz `--logfile` lines with stats. It has no periodic stat line like
+/// libFuzzer, new coverage is logged as it's found and the rest on exit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line {
+    Coverage {
+        /// Edges covered so far, the `e` in `(i/b/h/e/p/c)`.
+        edges: u32,
+    },
+    Summary {
+        iterations: u64,
+        /// Iterations per second.
+        speed: u32,
+        crashes: u32,
+        timeouts: u32,
+        peak_rss_mb: u64,
+    },
+}
+
+impl Line {
+    pub fn from_log(log: &str) -> Result<Line, ParseError<&str, ContextError>> {
+        alt((parse_coverage, parse_summary)).parse(log)
+    }
+
+    /// Maps the line onto the libFuzzer stats, the fields it doesn't carry
+    /// keep their values from `prev`.
+    pub fn into_parsed(self, prev: Parsed) -> Parsed {
+        match self {
+            Line::Coverage { edges } => Parsed {
+                cov: edges,
+                kind: Some(StatKind::New),
+                ..prev
+            },
+            Line::Summary {
+                iterations,
+                speed,
+                crashes,
+                timeouts,
+                peak_rss_mb,
+            } => Parsed {
+                total_execs: iterations,
+                exec_s: speed,
+                crash: crashes,
+                timeout: timeouts,
+                rss: peak_rss_mb * 1024 * 1024,
+                kind: Some(StatKind::Done),
+                ..prev
+            },
+        }
+    }
+}
+
+// [2025-02-20T08:24:30+0000][I][117394] Sz:1024 Tm:1,234us (i/b/h/e/p/c) New:0/0/0/12/0/3, Cur:0/0/0/2163/0/20854
+fn parse_coverage(input: &mut &str) -> Result<Line> {
+    take_until(0.., "Sz:").void().parse_next(input)?;
+    take_until(0.., "Cur:").void().parse_next(input)?;
+    // i/b/h/e/p/c, instructions, branches, blocks, edges, pcs, cmps
+    let edges = preceded(
+        ("Cur:", space0, digit1, '/', digit1, '/', digit1, '/'),
+        dec_uint,
+    )
+    .parse_next(input)?;
+    rest.void().parse_next(input)?;
+
+    Ok(Line::Coverage { edges })
+}
+
+// [2025-02-20T08:24:30+0000][I][117394] Summary iterations:2903021 time:3600 speed:806 crashes_count:1 timeout_count:2 new_units_added:345 slowest_unit_ms:1200 guard_nb:98765 branch_coverage_percent:12 peak_rss_mb:412
+fn parse_summary(input: &mut &str) -> Result<Line> {
+    take_until(0.., "Summary iterations:")
+        .void()
+        .parse_next(input)?;
+    let iterations = preceded("Summary iterations:", dec_uint).parse_next(input)?;
+    let speed =
+        preceded((" time:", dec_uint::<_, u64, _>, " speed:"), dec_uint).parse_next(input)?;
+    let crashes = preceded(" crashes_count:", dec_uint).parse_next(input)?;
+    let timeouts = preceded(" timeout_count:", dec_uint).parse_next(input)?;
+    take_until(0.., " peak_rss_mb:").void().parse_next(input)?;
+    // too large for a u64 in bytes, it's a corrupt line
+    let peak_rss_mb = preceded(" peak_rss_mb:", dec_uint)
+        .verify(|mb: &u64| mb.checked_mul(1024 * 1024).is_some())
+        .parse_next(input)?;
+    rest.void().parse_next(input)?;
+
+    Ok(Line::Summary {
+        iterations,
+        speed,
+        crashes,
+        timeouts,
+        peak_rss_mb,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::Line;
+    use crate::{Parsed, StatKind};
+
+    #[test]
+    fn test_parse_honggfuzz() {
+        let log = "[2025-02-20T08:24:30+0000][I][117394] Sz:1024 Tm:1,234us (i/b/h/e/p/c) New:0/0/0/12/0/3, Cur:0/0/0/2163/0/20854";
+        let line = Line::from_log(log).unwrap();
+        assert_eq!(line, Line::Coverage { edges: 2163 });
+        let prev = Parsed {
+            cov: 2000,
+            exec_s: 806,
+            ..Default::default()
+        };
+        assert_eq!(
+            line.into_parsed(prev),
+            Parsed {
+                cov: 2163,
+                exec_s: 806,
+                kind: Some(StatKind::New),
+                ..Default::default()
+            }
+        );
+
+        let log = "[2025-02-20T08:24:30+0000][I][117394] Summary iterations:2903021 time:3600 speed:806 crashes_count:1 timeout_count:2 new_units_added:345 slowest_unit_ms:1200 guard_nb:98765 branch_coverage_percent:12 peak_rss_mb:412";
+        let line = Line::from_log(log).unwrap();
+        assert_eq!(
+            line,
+            Line::Summary {
+                iterations: 2903021,
+                speed: 806,
+                crashes: 1,
+                timeouts: 2,
+                peak_rss_mb: 412,
+            }
+        );
+        let prev = Parsed {
+            cov: 2163,
+            ..Default::default()
+        };
+        assert_eq!(
+            line.into_parsed(prev),
+            Parsed {
+                cov: 2163,
+                total_execs: 2903021,
+                exec_s: 806,
+                crash: 1,
+                timeout: 2,
+                rss: 412 * 1024 * 1024,
+                kind: Some(StatKind::Done),
+                ..Default::default()
+            }
+        );
+
+        assert!(
+            Line::from_log("[2025-02-20T08:24:30+0000][I][117394] Launched 8 threads").is_err()
+        );
+        let log = log.replace("peak_rss_mb:412", "peak_rss_mb:99999999999999");
+        assert!(Line::from_log(&log).is_err());
+    }
+}
@@ -0,0 +1,405 @@
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, HeaderValue, WWW_AUTHENTICATE};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode, Uri};
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use std::net::SocketAddr;
+use std::path::Path;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+pub type HealthCheck = Arc<dyn Fn() -> bool + Send + Sync>;
+/// Renders the metrics in the Prometheus text format.
+pub type Render = Arc<dyn Fn() -> String + Send + Sync>;
+/// Renders the metrics in the Prometheus protobuf format, the only one with
+/// native histograms.
+pub type RenderProtobuf = Arc<dyn Fn() -> Vec<u8> + Send + Sync>;
+
+const PROTOBUF_CONTENT_TYPE: &str =
+    "application/vnd.google.protobuf; proto=io.prometheus.client.MetricFamily; encoding=delimited";
+
+/// Where the metrics are served.
+pub enum Listener {
+    Tcp(TcpListener),
+    /// Removes the socket file when dropped, which tasks are on shutdown.
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, PathBuf),
+}
+
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+impl Listener {
+    pub async fn tcp(addr: SocketAddr) -> anyhow::Result<Listener> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("failed to start the metrics server on {addr}"))?;
+        Ok(Listener::Tcp(listener))
+    }
+
+    #[cfg(unix)]
+    pub fn unix(path: PathBuf) -> anyhow::Result<Listener> {
+        use std::os::unix::fs::FileTypeExt;
+
+        // left behind by an exporter that didn't shut down cleanly
+        let stale = std::fs::symlink_metadata(&path).is_ok_and(|meta| meta.file_type().is_socket());
+        if stale {
+            let _ = std::fs::remove_file(&path);
+        }
+        let listener = tokio::net::UnixListener::bind(&path)
+            .with_context(|| format!("failed to start the metrics server on {}", path.display()))?;
+        Ok(Listener::Unix(listener, path))
+    }
+
+    async fn accept(&self) -> std::io::Result<Box<dyn Connection>> {
+        Ok(match self {
+            Listener::Tcp(listener) => Box::new(listener.accept().await?.0),
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => Box::new(listener.accept().await?.0),
+        })
+    }
+}
+
+impl std::fmt::Display for Listener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Listener::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => addr.fmt(f),
+                Err(_) => f.write_str("TCP"),
+            },
+            #[cfg(unix)]
+            Listener::Unix(_, path) => path.display().fmt(f),
+        }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Listener::Unix(_, path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Serves HTTPS with the PEM certificate chain at `cert` and its key.
+pub fn tls_acceptor(cert: &Path, key: &Path) -> anyhow::Result<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("failed to read certificates from {}", cert.display()))?;
+    anyhow::ensure!(!certs.is_empty(), "no certificates in {}", cert.display());
+    let key = PrivateKeyDer::from_pem_file(key)
+        .with_context(|| format!("failed to read a private key from {}", key.display()))?;
+    let provider = tokio_rustls::rustls::crypto::aws_lc_rs::default_provider();
+    let config = ServerConfig::builder_with_provider(Arc::new(provider))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("invalid TLS certificate or key")?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Serves `/healthz` and the Prometheus text format on every other path,
+/// like the `PrometheusBuilder` listener does, over TLS with `tls`. Without
+/// `render`, metrics are pushed elsewhere and only `/healthz` is served.
+/// With `protobuf`, scrapers that ask for that format get it instead. With
+/// `token`, the metrics need it as a bearer token, health checks don't.
+pub async fn serve(
+    listener: Listener,
+    tls: Option<TlsAcceptor>,
+    token: Option<Arc<str>>,
+    render: Option<Render>,
+    protobuf: Option<RenderProtobuf>,
+    is_healthy: HealthCheck,
+) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!("failed to accept connection: {e}");
+                continue;
+            }
+        };
+
+        let render = render.clone();
+        let protobuf = protobuf.clone();
+        let is_healthy = is_healthy.clone();
+        let token = token.clone();
+        let service = service_fn(move |req: Request<Incoming>| {
+            let response = handle_request(
+                &req,
+                token.as_deref(),
+                render.as_deref(),
+                protobuf.as_deref(),
+                &*is_healthy,
+            );
+            async move { Ok::<_, hyper::Error>(response) }
+        });
+        let tls = tls.clone();
+        tokio::spawn(async move {
+            // the handshake too, a slow client mustn't hold up the others
+            let stream: Box<dyn Connection> = match tls {
+                Some(tls) => match tls.accept(stream).await {
+                    Ok(stream) => Box::new(stream),
+                    Err(e) => {
+                        tracing::warn!("TLS handshake failed: {e}");
+                        return;
+                    }
+                },
+                None => stream,
+            };
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::warn!("failed to serve connection: {e}");
+            }
+        });
+    }
+}
+
+/// Pushes the metrics to a Prometheus Pushgateway group at `url` every
+/// `interval`. A PUT replaces the whole group, so the series of removed jobs
+/// go away there too.
+pub async fn push(url: Uri, render: Render, interval: Duration) {
+    let https = match hyper_rustls::HttpsConnectorBuilder::new().with_native_roots() {
+        Ok(https) => https.https_or_http().enable_http1().build(),
+        Err(e) => {
+            tracing::warn!("failed to load the root certificates, not pushing metrics: {e}");
+            return;
+        }
+    };
+    let client: Client<_, Full<Bytes>> = Client::builder(TokioExecutor::new()).build(https);
+
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        ticks.tick().await;
+        let request = Request::put(url.clone())
+            .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+            .body(Full::from(render()));
+        let request = match request {
+            Ok(request) => request,
+            Err(e) => {
+                tracing::warn!("failed to build the push request: {e}");
+                continue;
+            }
+        };
+        match client.request(request).await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => {
+                tracing::warn!(status = %response.status(), "the pushgateway rejected the metrics");
+            }
+            Err(e) => tracing::warn!("failed to push metrics to {url}: {e}"),
+        }
+    }
+}
+
+fn handle_request<B>(
+    req: &Request<B>,
+    token: Option<&str>,
+    render: Option<&(dyn Fn() -> String + Send + Sync)>,
+    protobuf: Option<&(dyn Fn() -> Vec<u8> + Send + Sync)>,
+    is_healthy: &(dyn Fn() -> bool + Send + Sync),
+) -> Response<Full<Bytes>> {
+    let authorized = || {
+        let Some(token) = token else {
+            return true;
+        };
+        let bearer = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.as_bytes().strip_prefix(b"Bearer "));
+        bearer.is_some_and(|bearer| constant_time_eq(bearer, token.as_bytes()))
+    };
+    // Prometheus lists protobuf first once native histograms are enabled
+    let wants_protobuf = || {
+        let accept = req
+            .headers()
+            .get(ACCEPT)
+            .and_then(|value| value.to_str().ok());
+        accept.is_some_and(|accept| {
+            accept
+                .split(',')
+                .any(|mime| mime.trim().starts_with("application/vnd.google.protobuf"))
+        })
+    };
+    let text = |status, body: String| (status, body.into_bytes(), "text/plain");
+    let (status, body, content_type) = match req.uri().path() {
+        "/healthz" if is_healthy() => text(StatusCode::OK, "OK".to_owned()),
+        "/healthz" => text(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "no recent log lines".to_owned(),
+        ),
+        _ if !authorized() => text(
+            StatusCode::UNAUTHORIZED,
+            "missing or wrong token".to_owned(),
+        ),
+        _ => match (render, protobuf) {
+            (Some(_), Some(protobuf)) if wants_protobuf() => {
+                (StatusCode::OK, protobuf(), PROTOBUF_CONTENT_TYPE)
+            }
+            (Some(render), _) => text(StatusCode::OK, render()),
+            (None, _) => text(
+                StatusCode::NOT_FOUND,
+                "metrics are pushed to the --exporter, not served".to_owned(),
+            ),
+        },
+    };
+
+    let mut response = Response::new(Full::from(body));
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    if status == StatusCode::UNAUTHORIZED {
+        headers.insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    }
+    response
+}
+
+/// Compares without returning early at the first difference, which would
+/// tell how much of a guessed token is right. Only the length leaks.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Listener, handle_request, serve, tls_acceptor};
+    use hyper::{Request, StatusCode};
+    use std::path::Path;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::TlsConnector;
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName};
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+    #[tokio::test]
+    async fn test_serves_tls() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let (cert, key) = (fixtures.join("tls-cert.pem"), fixtures.join("tls-key.pem"));
+        let tls = tls_acceptor(&cert, &key).unwrap();
+        assert!(tls_acceptor(&cert, &cert).is_err());
+
+        let listener = Listener::tcp("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let Listener::Tcp(tcp) = &listener else {
+            unreachable!()
+        };
+        let addr = tcp.local_addr().unwrap();
+        let render = Arc::new(|| "fuzz_cov 2163\n".to_owned());
+        tokio::spawn(serve(
+            listener,
+            Some(tls),
+            None,
+            Some(render),
+            None,
+            Arc::new(|| true),
+        ));
+
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(CertificateDer::from_pem_file(&cert).unwrap())
+            .unwrap();
+        let provider = tokio_rustls::rustls::crypto::aws_lc_rs::default_provider();
+        let config = ClientConfig::builder_with_provider(Arc::new(provider))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let name = ServerName::try_from("localhost").unwrap();
+        let mut stream = TlsConnector::from(Arc::new(config))
+            .connect(name, tcp)
+            .await
+            .unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with("fuzz_cov 2163\n"), "{response}");
+    }
+
+    #[test]
+    fn test_requires_token() {
+        let render = || "fuzz_cov 2163\n".to_owned();
+        let status = |path: &str, authorization: Option<&str>| {
+            let mut req = Request::get(path);
+            if let Some(authorization) = authorization {
+                req = req.header("Authorization", authorization);
+            }
+            let req = req.body(()).unwrap();
+            handle_request(&req, Some("s3cret"), Some(&render), None, &|| true).status()
+        };
+        assert_eq!(status("/metrics", Some("Bearer s3cret")), StatusCode::OK);
+        assert_eq!(status("/metrics", None), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status("/metrics", Some("Bearer s3cre")),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status("/metrics", Some("Bearer s3cret2")),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status("/metrics", Some("Basic s3cret")),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(status("/healthz", None), StatusCode::OK);
+
+        let req = Request::get("/metrics").body(()).unwrap();
+        let response = handle_request(&req, None, Some(&render), None, &|| true);
+        assert_eq!(response.status(), StatusCode::OK);
+        // pushed with --exporter statsd or otlp
+        let response = handle_request(&req, None, None, None, &|| true);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_negotiates_protobuf() {
+        use http_body_util::BodyExt;
+
+        let render = || "fuzz_cov 2163\n".to_owned();
+        let protobuf = || b"\x0a\x08fuzz_cov".to_vec();
+        let scrape =
+            async |accept: &str, protobuf: Option<&(dyn Fn() -> Vec<u8> + Send + Sync)>| {
+                let req = Request::get("/metrics")
+                    .header("Accept", accept)
+                    .body(())
+                    .unwrap();
+                let response = handle_request(&req, None, Some(&render), protobuf, &|| true);
+                let content_type = response.headers()["Content-Type"]
+                    .to_str()
+                    .unwrap()
+                    .to_owned();
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                (content_type, body.to_vec())
+            };
+        // what Prometheus sends with native histograms enabled
+        let prometheus = "application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;encoding=delimited;q=0.5,text/plain;version=0.0.4;q=0.4,*/*;q=0.1";
+        let (content_type, body) = scrape(prometheus, Some(&protobuf)).await;
+        assert!(content_type.starts_with("application/vnd.google.protobuf"));
+        assert_eq!(body, protobuf());
+
+        let (content_type, body) = scrape("text/plain;version=0.0.4", Some(&protobuf)).await;
+        assert_eq!(content_type, "text/plain");
+        assert_eq!(body, render().into_bytes());
+        let (_, body) = scrape(prometheus, None).await;
+        assert_eq!(body, render().into_bytes());
+    }
+}
@@ -0,0 +1,102 @@
+use metrics::{Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long the Prometheus recorder keeps a series without an update, a lot
+/// longer than a render takes. Histograms can't be updated without a sample,
+/// one that gets none for that long, like a stalled job's, is dropped too
+/// and starts over with the next.
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Keeps the counters and gauges it hands out updated, for a recorder that
+/// drops idle ones, until [`Series::refresh`] lets go of them, like those of
+/// removed jobs, which then age out. Histograms are left to the inner
+/// recorder, see [`IDLE_TIMEOUT`].
+pub struct KeepFresh<R> {
+    inner: R,
+    series: Series,
+}
+
+/// The series [`KeepFresh`] handed out.
+#[derive(Clone, Default)]
+pub struct Series(Arc<Mutex<Handles>>);
+
+#[derive(Default)]
+struct Handles {
+    counters: HashMap<Key, Counter>,
+    gauges: HashMap<Key, Gauge>,
+}
+
+impl<R> KeepFresh<R> {
+    pub fn new(inner: R) -> (Self, Series) {
+        let series = Series::default();
+        let recorder = Self {
+            inner,
+            series: series.clone(),
+        };
+        (recorder, series)
+    }
+}
+
+impl Series {
+    /// Updates every series without changing its value, before each render
+    /// so none goes idle, except those `stale` picks, which are let go of.
+    pub fn refresh(&self, stale: impl Fn(&Key) -> bool) {
+        let mut handles = self.handles();
+        handles.counters.retain(|key, counter| {
+            let keep = !stale(key);
+            if keep {
+                counter.increment(0);
+            }
+            keep
+        });
+        handles.gauges.retain(|key, gauge| {
+            let keep = !stale(key);
+            if keep {
+                gauge.increment(0.0);
+            }
+            keep
+        });
+    }
+
+    fn handles(&self) -> std::sync::MutexGuard<'_, Handles> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<R: Recorder> Recorder for KeepFresh<R> {
+    fn describe_counter(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_counter(key, unit, description);
+    }
+
+    fn describe_gauge(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_gauge(key, unit, description);
+    }
+
+    fn describe_histogram(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_histogram(key, unit, description);
+    }
+
+    fn register_counter(&self, key: &Key, metadata: &Metadata<'_>) -> Counter {
+        let mut handles = self.series.handles();
+        handles
+            .counters
+            .entry(key.clone())
+            .or_insert_with(|| self.inner.register_counter(key, metadata))
+            .clone()
+    }
+
+    fn register_gauge(&self, key: &Key, metadata: &Metadata<'_>) -> Gauge {
+        let mut handles = self.series.handles();
+        handles
+            .gauges
+            .entry(key.clone())
+            .or_insert_with(|| self.inner.register_gauge(key, metadata))
+            .clone()
+    }
+
+    fn register_histogram(&self, key: &Key, metadata: &Metadata<'_>) -> Histogram {
+        self.inner.register_histogram(key, metadata)
+    }
+}
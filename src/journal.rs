@@ -0,0 +1,199 @@
+use crate::{Args, fork_mode_line};
+use fuzz_exporter::LineClassifier;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Child;
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Follows the fuzz units through `journalctl -o json`, every entry is
+/// labeled with the unit it came from. journalctl is restarted with backoff
+/// whenever it exits, resuming after the last entry.
+pub async fn journalctl_parser(args: &Args) -> anyhow::Result<()> {
+    tracing::info!(units = ?args.journal_unit, "starting journalctl parser");
+
+    let mut cursor = None;
+    let mut journalctl = spawn(args, None)?;
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        let last_cursor = cursor.clone();
+        let mut reason = match follow(args, &mut journalctl, &mut cursor).await {
+            Ok(()) => match journalctl.wait().await {
+                Ok(status) => format!("journalctl exited with {status}"),
+                Err(e) => format!("journalctl exited: {e}"),
+            },
+            Err(e) => format!("failed to read journalctl output: {e}"),
+        };
+        // only a journalctl that keeps failing right away is backed off
+        if cursor != last_cursor {
+            backoff = MIN_BACKOFF;
+        }
+        loop {
+            tracing::warn!("{reason}, restarting it in {backoff:?}");
+            metrics::counter!("journal_restarts_total").increment(1);
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            match spawn(args, cursor.as_deref()) {
+                Ok(restarted) => {
+                    journalctl = restarted;
+                    break;
+                }
+                Err(e) => reason = format!("{e:?}"),
+            }
+        }
+    }
+}
+
+/// Starts `journalctl -f`, after the entry at `cursor` if there is one.
+fn spawn(args: &Args, cursor: Option<&str>) -> anyhow::Result<Child> {
+    let mut journalctl = tokio::process::Command::new("journalctl");
+    journalctl
+        .arg(if args.system { "--system" } else { "--user" })
+        .args(["-f", "-o", "json"]);
+    if let Some(cursor) = cursor {
+        journalctl.arg(format!("--after-cursor={cursor}"));
+    }
+    for unit in &args.journal_unit {
+        journalctl.arg("-u").arg(unit);
+    }
+    journalctl
+        .args(&args.journal_match)
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => anyhow::anyhow!("journalctl not found in PATH"),
+            _ => anyhow::Error::new(e).context("failed to spawn journalctl"),
+        })
+}
+
+/// Publishes the entries journalctl prints until its output ends, keeping
+/// the cursor of the last one.
+async fn follow(
+    args: &Args,
+    journalctl: &mut Child,
+    cursor: &mut Option<String>,
+) -> std::io::Result<()> {
+    let Some(stdout) = journalctl.stdout.take() else {
+        return Ok(());
+    };
+    let mut lines = BufReader::new(stdout).lines();
+    // the units' reports are interleaved
+    let mut classifiers: HashMap<String, LineClassifier> = HashMap::new();
+    while let Some(line) = lines.next_line().await? {
+        let entry = match Entry::from_json(&line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::warn!("failed to parse journalctl output: {e}");
+                continue;
+            }
+        };
+        if entry.cursor.is_some() {
+            *cursor = entry.cursor;
+        }
+        let Some(message) = entry.message else {
+            continue;
+        };
+        let unit = entry.user_unit.or(entry.unit).unwrap_or_default();
+        let classifier = classifiers.entry(unit.clone()).or_default();
+        fork_mode_line(
+            args,
+            &message,
+            classifier,
+            &[("unit", unit)],
+            entry.realtime_us,
+        );
+    }
+
+    Ok(())
+}
+
+/// The fields of a journal entry the exporter uses.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+struct Entry {
+    /// Lossily decoded, journalctl sends messages that aren't valid UTF-8
+    /// as arrays of bytes.
+    #[serde(rename = "MESSAGE", default, deserialize_with = "text_or_bytes")]
+    message: Option<String>,
+    #[serde(rename = "_SYSTEMD_UNIT")]
+    unit: Option<String>,
+    /// Set for user services, whose `_SYSTEMD_UNIT` is the user manager.
+    #[serde(rename = "_SYSTEMD_USER_UNIT")]
+    user_unit: Option<String>,
+    /// Microseconds since the epoch, journalctl sends numbers as strings.
+    #[serde(
+        rename = "__REALTIME_TIMESTAMP",
+        default,
+        deserialize_with = "from_str"
+    )]
+    realtime_us: Option<u64>,
+    /// Where to resume from after a restart.
+    #[serde(rename = "__CURSOR")]
+    cursor: Option<String>,
+}
+
+impl Entry {
+    fn from_json(line: &str) -> serde_json::Result<Entry> {
+        serde_json::from_str(line)
+    }
+}
+
+fn from_str<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn text_or_bytes<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Field {
+        Text(String),
+        Bytes(Vec<u8>),
+    }
+
+    Ok(
+        Option::<Field>::deserialize(deserializer)?.map(|field| match field {
+            Field::Text(text) => text,
+            Field::Bytes(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::Entry;
+
+    #[test]
+    fn test_parse_entry() {
+        let line = r#"{"__CURSOR":"s=1a;i=2b","__REALTIME_TIMESTAMP":"1740039870000000","_SYSTEMD_UNIT":"fuzz-a.service","MESSAGE":"cov: 2163 ft: 20854","_PID":"117394"}"#;
+        assert_eq!(
+            Entry::from_json(line).unwrap(),
+            Entry {
+                message: Some("cov: 2163 ft: 20854".to_string()),
+                unit: Some("fuzz-a.service".to_string()),
+                user_unit: None,
+                realtime_us: Some(1740039870000000),
+                cursor: Some("s=1a;i=2b".to_string()),
+            }
+        );
+
+        let line = r#"{"_SYSTEMD_UNIT":"user@1000.service","_SYSTEMD_USER_UNIT":"fuzz-b.service","MESSAGE":"x"}"#;
+        assert_eq!(
+            Entry::from_json(line).unwrap().user_unit.as_deref(),
+            Some("fuzz-b.service")
+        );
+        let line = r#"{"MESSAGE":[99,111,118,58,255]}"#;
+        assert_eq!(
+            Entry::from_json(line).unwrap().message.as_deref(),
+            Some("cov:\u{fffd}")
+        );
+        assert_eq!(Entry::from_json("{}").unwrap(), Entry::default());
+        assert!(Entry::from_json(r#"{"__REALTIME_TIMESTAMP":"soon"}"#).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use metrics::{
+    Counter, Gauge, Histogram, Key, KeyName, Label, Metadata, Recorder, SharedString, Unit,
+};
+
+/// Adds the same labels to every metric, like the `host` one of
+/// `--host-label`.
+pub struct GlobalLabels<R> {
+    inner: R,
+    labels: Vec<Label>,
+}
+
+impl<R> GlobalLabels<R> {
+    pub fn new(inner: R, labels: Vec<Label>) -> Self {
+        Self { inner, labels }
+    }
+
+    fn key(&self, key: &Key) -> Key {
+        key.with_extra_labels(self.labels.clone())
+    }
+}
+
+/// Renames the `job` label of every series to `name`: `exported_job` for
+/// the Pushgateway, which rejects series that set a label of the grouping
+/// key, like Prometheus does on a clash.
+pub struct JobLabel<R> {
+    inner: R,
+    name: &'static str,
+}
+
+impl<R> JobLabel<R> {
+    pub fn new(inner: R, name: &'static str) -> Self {
+        Self { inner, name }
+    }
+
+    fn key(&self, key: &Key) -> Key {
+        if self.name == "job" {
+            return key.clone();
+        }
+        let labels = key.labels().map(|label| match label.key() {
+            "job" => Label::new(self.name, label.value().to_owned()),
+            _ => label.clone(),
+        });
+        Key::from_parts(key.name_shared(), labels.collect::<Vec<_>>())
+    }
+}
+
+impl<R: Recorder> Recorder for JobLabel<R> {
+    fn describe_counter(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_counter(key, unit, description);
+    }
+
+    fn describe_gauge(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_gauge(key, unit, description);
+    }
+
+    fn describe_histogram(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_histogram(key, unit, description);
+    }
+
+    fn register_counter(&self, key: &Key, metadata: &Metadata<'_>) -> Counter {
+        self.inner.register_counter(&self.key(key), metadata)
+    }
+
+    fn register_gauge(&self, key: &Key, metadata: &Metadata<'_>) -> Gauge {
+        self.inner.register_gauge(&self.key(key), metadata)
+    }
+
+    fn register_histogram(&self, key: &Key, metadata: &Metadata<'_>) -> Histogram {
+        self.inner.register_histogram(&self.key(key), metadata)
+    }
+}
+
+impl<R: Recorder> Recorder for GlobalLabels<R> {
+    fn describe_counter(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_counter(key, unit, description);
+    }
+
+    fn describe_gauge(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_gauge(key, unit, description);
+    }
+
+    fn describe_histogram(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_histogram(key, unit, description);
+    }
+
+    fn register_counter(&self, key: &Key, metadata: &Metadata<'_>) -> Counter {
+        self.inner.register_counter(&self.key(key), metadata)
+    }
+
+    fn register_gauge(&self, key: &Key, metadata: &Metadata<'_>) -> Gauge {
+        self.inner.register_gauge(&self.key(key), metadata)
+    }
+
+    fn register_histogram(&self, key: &Key, metadata: &Metadata<'_>) -> Histogram {
+        self.inner.register_histogram(&self.key(key), metadata)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GlobalLabels, JobLabel};
+    use metrics::Label;
+    use metrics_util::debugging::DebuggingRecorder;
+
+    #[test]
+    fn test_renames_job_label() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let recorder = JobLabel::new(recorder, "exported_job");
+        metrics::with_local_recorder(&recorder, || {
+            metrics::gauge!("cov", "job" => "fuzz-0").set(10.0);
+            metrics::counter!("artifacts_total", "hash" => "job=\"x\"", "job" => "x").absolute(1);
+            metrics::gauge!("cov").set(10.0);
+        });
+
+        let mut series: Vec<_> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, _)| {
+                let labels = key.key().labels();
+                let labels: Vec<_> = labels
+                    .map(|l| format!("{}={}", l.key(), l.value()))
+                    .collect();
+                (key.key().name().to_owned(), labels)
+            })
+            .collect();
+        series.sort();
+        assert_eq!(
+            series,
+            [
+                (
+                    "artifacts_total".to_owned(),
+                    vec!["hash=job=\"x\"".to_owned(), "exported_job=x".to_owned()]
+                ),
+                ("cov".to_owned(), vec![]),
+                ("cov".to_owned(), vec!["exported_job=fuzz-0".to_owned()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_adds_labels() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let recorder = GlobalLabels::new(recorder, vec![Label::new("host", "fuzz-1")]);
+        metrics::with_local_recorder(&recorder, || {
+            metrics::gauge!("cov", "job" => "fuzz-0").set(10.0);
+            metrics::counter!("jobs_total").increment(1);
+        });
+
+        let mut series: Vec<_> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, _)| {
+                let labels = key.key().labels();
+                let labels: Vec<_> = labels
+                    .map(|l| format!("{}={}", l.key(), l.value()))
+                    .collect();
+                (key.key().name().to_owned(), labels)
+            })
+            .collect();
+        series.sort();
+        assert_eq!(
+            series,
+            [
+                (
+                    "cov".to_owned(),
+                    vec!["job=fuzz-0".to_owned(), "host=fuzz-1".to_owned()]
+                ),
+                ("jobs_total".to_owned(), vec!["host=fuzz-1".to_owned()]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1185 @@
+//! Parsers for libFuzzer and honggfuzz log lines, the `fuzz-exporter`
+//! binary publishes what they return as metrics.
+//!
+//! ```
+//! let parsed = fuzz_exporter::Parsed::from_log_job(
+//!     "#1024\tNEW    cov: 10 ft: 11 corp: 3/512Kb exec/s: 0 rss: 30Mb",
+//! )
+//! .unwrap();
+//! assert_eq!(parsed.cov, 10);
+//! assert_eq!(parsed.corp_size, 512 * 1024);
+//! ```
+
+pub mod honggfuzz;
+
+use chrono::{Datelike, TimeZone};
+use futures::Stream;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use winnow::Result;
+use winnow::ascii::{Caseless, alpha1, dec_uint, digit1, space0, space1};
+use winnow::combinator::{alt, opt, preceded, terminated};
+use winnow::error::{ContextError, ParseError};
+use winnow::token::{rest, take_till, take_until, take_while};
+
+use winnow::prelude::*;
+
+// Feb 20 08:24:30 test-server-1 cargo[117394]: #2903021619: cov: 2163 ft: 20854 corp: 2853 exec/s: 1464 oom/timeout/crash: 0/0/0 time: 56383s job: 6125 dft_time: 0
+/// Stats from a libFuzzer stat line. Fields the line doesn't carry are 0,
+/// fork mode has no sizes and job mode no oom/timeout/crash counters.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Parsed {
+    /// Covered edges, `cov:`.
+    pub cov: u32,
+    /// Features, `ft:`.
+    pub ft: u32,
+    /// Inputs in the corpus, `corp:`.
+    pub corp: u32,
+    /// Corpus size in bytes.
+    pub corp_size: u64,
+    /// Unit `corp_size` was printed in, older dashboards want it as is.
+    pub corp_size_unit: Option<SizeUnit>,
+    /// Input length limit, `lim:`.
+    pub lim: u32,
+    /// Corpus inputs that reach the `-focus_function`, `focus:`. Only
+    /// printed once libFuzzer observed the function, `None` otherwise.
+    pub focus: Option<u32>,
+    /// Executions per second, averaged over the run.
+    pub exec_s: u32,
+    /// Executions so far, the leading `#` counter, 0 when the line has none.
+    pub total_execs: u64,
+    /// Why a job-mode stat line was printed.
+    pub kind: Option<StatKind>,
+    /// Resident memory in bytes.
+    pub rss: u64,
+    pub oom: u32,
+    pub timeout: u32,
+    pub crash: u32,
+    /// Seconds since the fork-mode run started.
+    pub time: u32,
+    /// Fork-mode jobs started so far.
+    pub job: u32,
+    /// Seconds spent collecting data-flow traces.
+    pub dft_time: u32,
+    /// Leading syslog timestamp, when the line came through journald.
+    pub timestamp: Option<SyslogTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatKind {
+    Inited,
+    /// An input with new coverage was added to the corpus.
+    New,
+    Reduce,
+    Pulse,
+    Reload,
+    Done,
+}
+
+/// `Feb 20 08:24:30`, in local time and without a year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyslogTime {
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl SyslogTime {
+    /// Seconds since the timestamp, assuming it's from the last year.
+    pub fn age(&self, now: chrono::DateTime<chrono::Local>) -> Option<i64> {
+        let at = |year| {
+            let date = chrono::NaiveDate::from_ymd_opt(year, self.month, self.day)?;
+            let time = date.and_hms_opt(self.hour, self.minute, self.second)?;
+            chrono::Local.from_local_datetime(&time).earliest()
+        };
+        let mut timestamp = at(now.year())?;
+        // a line from Dec 31 read on Jan 1
+        if timestamp > now + chrono::TimeDelta::days(1) {
+            timestamp = at(now.year() - 1)?;
+        }
+        Some((now - timestamp).num_seconds())
+    }
+}
+
+impl Parsed {
+    /// Parses a fork-mode line, see [`parse_fork_mode`].
+    pub fn from_log(log: &str) -> Result<Parsed, ParseError<&str, ContextError>> {
+        parse_fork_mode.parse(log)
+    }
+
+    /// Parses a job-mode line, see [`parse_job_mode`].
+    pub fn from_log_job(log: &str) -> Result<Parsed, ParseError<&str, ContextError>> {
+        parse_job_mode.parse(log)
+    }
+}
+
+/// Why [`parse_stream`] has no stats for a line.
+#[derive(Debug)]
+pub enum StreamError {
+    /// Reading failed, the stream ends with it.
+    Io(std::io::Error),
+    /// A line that isn't a stat line, for the other parsers.
+    Unparsed(String),
+}
+
+impl std::fmt::Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "failed to read: {e}"),
+            StreamError::Unparsed(line) => write!(f, "not a stat line: {line}"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StreamError::Io(e) => Some(e),
+            StreamError::Unparsed(_) => None,
+        }
+    }
+}
+
+/// The stats of every line `reader` yields, fork-mode or job-mode ones, as
+/// they arrive.
+pub fn parse_stream(
+    reader: impl AsyncBufRead + Unpin,
+) -> impl Stream<Item = Result<Parsed, StreamError>> {
+    futures::stream::unfold(Some(reader.lines()), |lines| async move {
+        let mut lines = lines?;
+        match lines.next_line().await {
+            Ok(Some(line)) => {
+                // the stricter parser first, job mode would take `exec/s:`
+                // fork lines
+                let parsed = Parsed::from_log(&line)
+                    .or_else(|_| Parsed::from_log_job(&line))
+                    .ok();
+                let parsed = parsed.ok_or(StreamError::Unparsed(line));
+                Some((parsed, Some(lines)))
+            }
+            Ok(None) => None,
+            Err(e) => Some((Err(StreamError::Io(e)), None)),
+        }
+    })
+}
+
+/// `#2903021619: cov: 2163 ...` as printed by `-fork=N`, optionally behind a
+/// syslog prefix.
+pub fn parse_fork_mode(input: &mut &str) -> Result<Parsed> {
+    let timestamp = opt(parse_syslog_prefix).parse_next(input)?;
+
+    // 1. Execution counter, "#2903021619:"
+    let total_execs = terminated(preceded('#', dec_uint), (':', space1)).parse_next(input)?;
+
+    let cov = preceded(("cov:", space1), dec_uint).parse_next(input)?;
+    let ft = preceded((space1, ("ft:", space1)), dec_uint).parse_next(input)?;
+    let corp = preceded((space1, ("corp:", space1)), dec_uint).parse_next(input)?;
+
+    // somehow it can both variants of exec/s
+    let exec_s = alt((("exec/s", space1), ("exec/s:", space1)));
+    let exec_s = preceded((space1, exec_s), dec_uint).parse_next(input)?;
+
+    // 3. Parse OOM/Timeout/Crash
+    let oom_crash = preceded(
+        (space1, "oom/timeout/crash:", space1),
+        (dec_uint, '/', dec_uint, '/', dec_uint).map(|(o, _, t, _, c)| (o, t, c)),
+    )
+    .parse_next(input)?;
+
+    // 4. Parse time with 's' suffix
+    let time =
+        preceded((space1, ("time:", space1)), terminated(dec_uint, 's')).parse_next(input)?;
+
+    // 5. Fork job counter and data-flow-trace time
+    let job = opt(preceded((space1, "job:", space1), dec_uint))
+        .map(|job| job.unwrap_or(0))
+        .parse_next(input)?;
+    let dft_time = opt(preceded((space1, "dft_time:", space1), dec_uint))
+        .map(|dft_time| dft_time.unwrap_or(0))
+        .parse_next(input)?;
+    rest.void().parse_next(input)?;
+
+    Ok(Parsed {
+        cov,
+        ft,
+        corp,
+        corp_size: 0,
+        corp_size_unit: None,
+        lim: 0,
+        focus: None,
+        exec_s,
+        total_execs,
+        kind: None,
+        rss: 0,
+        oom: oom_crash.0,
+        timeout: oom_crash.1,
+        crash: oom_crash.2,
+        time,
+        job,
+        dft_time,
+        timestamp,
+    })
+}
+
+/// `Feb 20 08:24:30 test-server-1 cargo[117394]: `, as in `journalctl`
+/// text output piped to stdin.
+fn parse_syslog_prefix(input: &mut &str) -> Result<SyslogTime> {
+    let timestamp = terminated(parse_syslog_time, space1).parse_next(input)?;
+    let hostname = take_till(1.., ' ');
+    let identifier = take_till(1.., ['[', ':', ' ']);
+    let pid = opt(('[', digit1, ']'));
+    (hostname, space1, identifier, pid, ':', space1)
+        .void()
+        .parse_next(input)?;
+    Ok(timestamp)
+}
+
+fn parse_syslog_time(input: &mut &str) -> Result<SyslogTime> {
+    let month = alt((
+        "Jan".value(1),
+        "Feb".value(2),
+        "Mar".value(3),
+        "Apr".value(4),
+        "May".value(5),
+        "Jun".value(6),
+        "Jul".value(7),
+        "Aug".value(8),
+        "Sep".value(9),
+        "Oct".value(10),
+        "Nov".value(11),
+        "Dec".value(12),
+    ));
+    // dec_uint stops after a leading zero, as in 08
+    let (month, day, hour, minute, second) = (
+        terminated(month, space1),
+        terminated(digit1.parse_to(), space1),
+        terminated(digit1.parse_to(), ':'),
+        terminated(digit1.parse_to(), ':'),
+        digit1.parse_to(),
+    )
+        .parse_next(input)?;
+
+    Ok(SyslogTime {
+        month,
+        day,
+        hour,
+        minute,
+        second,
+    })
+}
+
+//  RELOAD cov: 641 ft: 9191 corp: 1640/591Kb lim: 2411 exec/s: 529 rss: 36Mb
+/// The stat lines a single libFuzzer process prints, as in the `-jobs=N` logs.
+pub fn parse_job_mode(input: &mut &str) -> Result<Parsed> {
+    // #<n> is the number of inputs executed so far
+    let total_execs = opt(preceded((space0, '#'), dec_uint))
+        .map(|execs| execs.unwrap_or(0))
+        .parse_next(input)?;
+
+    let kind = opt(preceded(space0, parse_stat_kind)).parse_next(input)?;
+
+    // 1. Skip everything until "cov:"
+    take_until(0.., "cov:").void().parse_next(input)?;
+
+    let cov = preceded(("cov:", space1), dec_uint).parse_next(input)?;
+    let ft = preceded((space1, "ft:", space1), dec_uint).parse_next(input)?;
+
+    // Parse corp: <units>[/<size><unit>]
+    let (corp_units, corp_size) = preceded(
+        (space1, "corp:", space1),
+        (dec_uint, opt(preceded('/', parse_sized))),
+    )
+    .parse_next(input)?;
+
+    let focus = opt(preceded((space1, "focus:", space1), dec_uint)).parse_next(input)?;
+
+    let lim = opt(preceded((space1, "lim:", space1), dec_uint))
+        .map(|lim| lim.unwrap_or(0))
+        .parse_next(input)?;
+
+    // Skip remaining fields until exec/s using proper delimiters
+    let _ = terminated(take_until(0.., "exec/s:"), "exec/s:").parse_next(input)?;
+
+    let exec_s = preceded(space1, dec_uint).parse_next(input)?;
+
+    // rss: XXMb
+    let rss = opt(preceded((space1, "rss:", space1), parse_size))
+        .map(|rss| rss.unwrap_or(0))
+        .parse_next(input)?;
+
+    // Skip the rest
+    rest.void().parse_next(input)?;
+
+    Ok(Parsed {
+        cov,
+        ft,
+        corp: corp_units,
+        corp_size: corp_size.map_or(0, |(n, unit)| n * unit.bytes()),
+        corp_size_unit: corp_size.map(|(_, unit)| unit),
+        lim,
+        focus,
+        exec_s,
+        total_execs,
+        kind,
+        rss,
+        oom: 0,
+        timeout: 0,
+        crash: 0,
+        time: 0,
+        job: 0,
+        dft_time: 0,
+        timestamp: None,
+    })
+}
+
+/// Job-mode logs don't carry oom/timeout/crash counters in their stat
+/// lines, so these are counted from the error reports instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Oom,
+    Timeout,
+    Crash,
+    /// The worker started, libFuzzer prints its seed first thing.
+    Restart,
+    /// A bounded run, as with `-runs=N`, finished.
+    Done,
+}
+
+impl Event {
+    /// Parses an error report, the seed banner or the final `Done` line.
+    pub fn from_log(log: &str) -> Result<Event, ParseError<&str, ContextError>> {
+        parse_event.parse(log)
+    }
+}
+
+// ==12345== ERROR: libFuzzer: deadly signal
+// ==12345==ERROR: AddressSanitizer: heap-buffer-overflow on address ...
+// ==12345== libFuzzer: out-of-memory (malloc(2147483648))
+// INFO: Seed: 3918206239
+// Done 1000 runs in 2 second(s)
+fn parse_event(input: &mut &str) -> Result<Event> {
+    let seed = preceded(
+        (take_until(0.., "INFO: Seed: "), "INFO: Seed: "),
+        digit1.value(Event::Restart),
+    );
+    let error = preceded(
+        (take_until(0.., "ERROR: "), "ERROR: "),
+        alt((
+            preceded(
+                "libFuzzer: ",
+                alt((
+                    "out-of-memory".value(Event::Oom),
+                    "timeout".value(Event::Timeout),
+                    "deadly signal".value(Event::Crash),
+                    "fuzz target exited".value(Event::Crash),
+                )),
+            ),
+            terminated(alpha1.verify(|name: &str| name.ends_with("Sanitizer")), ':')
+                .value(Event::Crash),
+        )),
+    );
+    // without `ERROR:`, but with the pid, `SUMMARY: libFuzzer: ...` repeats
+    // the same report
+    let pid_report = preceded(
+        ("==", digit1, "==", space0, "libFuzzer: "),
+        alt((
+            "out-of-memory".value(Event::Oom),
+            "timeout".value(Event::Timeout),
+        )),
+    );
+    let done = ("Done ", digit1, " runs in ", digit1, " second(s)").value(Event::Done);
+    let event = alt((error, pid_report, seed, done)).parse_next(input)?;
+    rest.void().parse_next(input)?;
+
+    Ok(event)
+}
+
+/// Lines libFuzzer prints besides stats and events, told apart from
+/// garbage so they don't count as parse errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuxLine {
+    /// `NEW_FUNC[1/2]: 0x55aa in parse_header src/parse.c:42`
+    NewFunc,
+    /// `MS: 2 ChangeBit-CopyPart-; base unit: ...` and the input dump after it.
+    Mutation,
+    /// Sanitizer reports, stack frames and crash artifacts.
+    Report,
+    /// `INFO:` lines, final `stat::` lines and the like.
+    Info,
+}
+
+impl AuxLine {
+    pub const ALL: [AuxLine; 4] = [
+        AuxLine::NewFunc,
+        AuxLine::Mutation,
+        AuxLine::Report,
+        AuxLine::Info,
+    ];
+
+    pub fn from_log(log: &str) -> Result<AuxLine, ParseError<&str, ContextError>> {
+        parse_aux_line.parse(log)
+    }
+
+    /// Value of the `kind` label.
+    pub fn name(self) -> &'static str {
+        match self {
+            AuxLine::NewFunc => "new_func",
+            AuxLine::Mutation => "mutation",
+            AuxLine::Report => "report",
+            AuxLine::Info => "info",
+        }
+    }
+}
+
+fn parse_aux_line(input: &mut &str) -> Result<AuxLine> {
+    let aux = preceded(
+        space0,
+        alt((
+            "NEW_FUNC".value(AuxLine::NewFunc),
+            alt(("MS: ", "base unit: ", "0x", "\\x")).value(AuxLine::Mutation),
+            alt((
+                "==",
+                "SUMMARY: ",
+                "artifact_prefix=",
+                "Test unit written to ",
+                "Base64: ",
+                // stack frame, `#0 0x55aa in main`
+                ('#', digit1, space1, "0x").void().take(),
+            ))
+            .value(AuxLine::Report),
+            alt(("INFO: ", "stat::", "Done ", "Running: ", "Executed ")).value(AuxLine::Info),
+        )),
+    )
+    .parse_next(input)?;
+    rest.void().parse_next(input)?;
+
+    Ok(aux)
+}
+
+/// Tells apart the lines of multi-line reports, which [`AuxLine::from_log`]
+/// can't one at a time: everything from a sanitizer or libFuzzer `ERROR:`
+/// banner up to `==N==ABORTING` or the mutation that caused it is part of
+/// the report, like `READ of size 1 at ...`, and so is the input printed as
+/// text after its hex dump.
+#[derive(Debug, Default)]
+pub struct LineClassifier {
+    in_report: bool,
+    after_dump: bool,
+}
+
+impl LineClassifier {
+    /// The kind of a line that isn't a stat line, `None` for garbage. Every
+    /// other line of the log has to be passed here too, in order.
+    pub fn classify(&mut self, log: &str) -> Option<AuxLine> {
+        if std::mem::take(&mut self.after_dump) {
+            return Some(AuxLine::Mutation);
+        }
+        if parse_report_start.parse(log).is_ok() {
+            self.in_report = true;
+        }
+        if self.in_report {
+            let ended = ["MS: ", "artifact_prefix="]
+                .iter()
+                .any(|end| log.starts_with(end));
+            if !ended {
+                self.in_report = !log.contains("==ABORTING");
+                return Some(AuxLine::Report);
+            }
+            self.in_report = false;
+        }
+        let aux = AuxLine::from_log(log).ok()?;
+        self.after_dump = aux == AuxLine::Mutation && log.trim_start().starts_with("0x");
+        Some(aux)
+    }
+
+    /// A stat line ends whatever report was printed.
+    pub fn reset(&mut self) {
+        *self = LineClassifier::default();
+    }
+}
+
+// =================================================================
+// ==12345==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011
+// ==12345==WARNING: MemorySanitizer: use-of-uninitialized-value
+// ==12345== ERROR: libFuzzer: deadly signal
+fn parse_report_start(input: &mut &str) -> Result<()> {
+    let sanitizer = alpha1.verify(|name: &str| name.ends_with("Sanitizer"));
+    let banner = (
+        ("==", digit1, "==", space0),
+        alt(("ERROR: ", "WARNING: ")),
+        alt(("libFuzzer", sanitizer)),
+        ':',
+    );
+    alt((take_while(16.., '=').void(), banner.void())).parse_next(input)?;
+    rest.void().parse_next(input)
+}
+
+/// Input libFuzzer saved for a crash, leak, oom or timeout, named after its
+/// SHA-1, which makes it findable from the metrics. The `metrics` facade has
+/// no exemplars, so it goes into a counter label instead. There are few of
+/// them as libFuzzer stops after the first one unless told otherwise.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Artifact {
+    /// `crash`, `leak`, `oom`, `timeout` or `slow-unit`.
+    pub kind: String,
+    pub hash: String,
+}
+
+impl Artifact {
+    /// Parses the line libFuzzer prints after saving an artifact.
+    pub fn from_log(log: &str) -> Result<Artifact, ParseError<&str, ContextError>> {
+        parse_artifact.parse(log)
+    }
+}
+
+// artifact_prefix='./'; Test unit written to ./crash-da39a3ee5e6b4b0d3255bfef95601890afd80709
+fn parse_artifact(input: &mut &str) -> Result<Artifact> {
+    preceded(
+        (
+            take_until(0.., "Test unit written to "),
+            "Test unit written to ",
+        ),
+        rest,
+    )
+    .verify_map(|path: &str| {
+        // the prefix can be any path
+        let name = path.trim_end().rsplit('/').next()?;
+        let (kind, hash) = name.rsplit_once('-')?;
+        let known = matches!(kind, "crash" | "leak" | "oom" | "timeout" | "slow-unit");
+        let sha1 = hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit());
+        (known && sha1).then(|| Artifact {
+            kind: kind.to_owned(),
+            hash: hash.to_owned(),
+        })
+    })
+    .parse_next(input)
+}
+
+/// Memory usage reported outside of stat lines, in bytes.
+pub fn rss_from_log(log: &str) -> Option<u64> {
+    parse_rss_report.parse(log).ok()
+}
+
+// ==12345== ERROR: libFuzzer: out-of-memory (used: 2050Mb; exceeds: 2048Mb)
+// stat::peak_rss_mb:              38
+fn parse_rss_report(input: &mut &str) -> Result<u64> {
+    let rss = alt((
+        preceded((take_until(0.., "(used: "), "(used: "), parse_size),
+        preceded(
+            (
+                take_until(0.., "stat::peak_rss_mb:"),
+                "stat::peak_rss_mb:",
+                space0,
+            ),
+            // too large for a u64 in bytes, it's a corrupt line
+            dec_uint.verify_map(|mb: u64| mb.checked_mul(1 << 20)),
+        ),
+    ))
+    .parse_next(input)?;
+    rest.void().parse_next(input)?;
+
+    Ok(rss)
+}
+
+/// Kind of the error a crash report is about, like `heap-buffer-overflow`:
+/// its words up to the address or location, joined by `-`.
+pub fn crash_type_from_log(log: &str) -> Option<String> {
+    parse_crash_type.parse(log).ok()
+}
+
+// ==12345==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x55aa
+// ==12345==ERROR: AddressSanitizer: SEGV on unknown address 0x000000000000 (pc 0x55aa T0)
+// ==12345== ERROR: libFuzzer: deadly signal
+fn parse_crash_type(input: &mut &str) -> Result<String> {
+    let sanitizer = terminated(alpha1.verify(|name: &str| name.ends_with("Sanitizer")), ':');
+    (
+        take_until(0.., "ERROR: "),
+        "ERROR: ",
+        alt(("libFuzzer:".void(), sanitizer.void())),
+        space1,
+    )
+        .void()
+        .parse_next(input)?;
+    rest.verify_map(|rest: &str| {
+        let words: Vec<&str> = rest
+            .split_whitespace()
+            .take_while(|word| {
+                !matches!(*word, "on" | "at" | "in" | "of" | "for" | "from")
+                    && !word.starts_with('(')
+                    && !word.contains(|c: char| c.is_ascii_digit())
+            })
+            .collect();
+        (!words.is_empty()).then(|| words.join("-"))
+    })
+    .parse_next(input)
+}
+
+/// Fuzz target name from the `INFO: Loaded N modules ... (name)` banner.
+pub fn target_from_log(log: &str) -> Option<&str> {
+    parse_target_banner.parse(log).ok()
+}
+
+// INFO: Loaded 1 modules   (2163 inline 8-bit counters): 2163 [0x55aa, 0x55bb), (fuzz_target_1)
+fn parse_target_banner<'a>(input: &mut &'a str) -> Result<&'a str> {
+    ("INFO: Loaded ", digit1, " module")
+        .void()
+        .parse_next(input)?;
+    rest.verify_map(|rest: &'a str| {
+        let (_, target) = rest.trim_end().strip_suffix(')')?.rsplit_once('(')?;
+        let valid = !target.is_empty() && !target.contains(char::is_whitespace);
+        valid.then_some(target)
+    })
+    .parse_next(input)
+}
+
+fn parse_stat_kind(input: &mut &str) -> Result<StatKind> {
+    terminated(
+        alt((
+            "INITED".value(StatKind::Inited),
+            "NEW".value(StatKind::New),
+            "REDUCE".value(StatKind::Reduce),
+            "pulse".value(StatKind::Pulse),
+            "RELOAD".value(StatKind::Reload),
+            "DONE".value(StatKind::Done),
+        )),
+        space1,
+    )
+    .parse_next(input)
+}
+
+/// Suffix of a libFuzzer size like `591Kb`, in powers of 1024.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeUnit {
+    B,
+    Kb,
+    Mb,
+    Gb,
+    Tb,
+}
+
+impl SizeUnit {
+    pub const ALL: [SizeUnit; 5] = [
+        SizeUnit::B,
+        SizeUnit::Kb,
+        SizeUnit::Mb,
+        SizeUnit::Gb,
+        SizeUnit::Tb,
+    ];
+
+    /// Bytes in one unit.
+    pub fn bytes(self) -> u64 {
+        1 << (10 * self as u32)
+    }
+
+    /// Value of the `unit` label, spelled the way libFuzzer prints it.
+    pub fn name(self) -> &'static str {
+        match self {
+            SizeUnit::B => "b",
+            SizeUnit::Kb => "Kb",
+            SizeUnit::Mb => "Mb",
+            SizeUnit::Gb => "Gb",
+            SizeUnit::Tb => "Tb",
+        }
+    }
+}
+
+// <n><unit>, e.g. 591Kb or 36Mb, in bytes. The casing of the unit varies,
+// `KB` and `kb` mean the same.
+fn parse_size(input: &mut &str) -> Result<u64> {
+    parse_sized
+        .map(|(n, unit)| n * unit.bytes())
+        .parse_next(input)
+}
+
+/// A size too large for a `u64` in bytes fails, it's a corrupt line.
+fn parse_sized(input: &mut &str) -> Result<(u64, SizeUnit)> {
+    (
+        dec_uint,
+        alt((
+            Caseless("kb").value(SizeUnit::Kb),
+            Caseless("mb").value(SizeUnit::Mb),
+            Caseless("gb").value(SizeUnit::Gb),
+            Caseless("tb").value(SizeUnit::Tb),
+            Caseless("b").value(SizeUnit::B),
+        )),
+    )
+        .verify(|&(n, unit): &(u64, SizeUnit)| n.checked_mul(unit.bytes()).is_some())
+        .parse_next(input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Artifact, AuxLine, Event, LineClassifier, Parsed, SizeUnit, StatKind, StreamError,
+        SyslogTime, crash_type_from_log, parse_event, parse_fork_mode, parse_job_mode, parse_size,
+        parse_stream, rss_from_log, target_from_log,
+    };
+    use futures::StreamExt;
+    use winnow::Parser;
+
+    #[test]
+    fn test_parse() {
+        let log = "Feb 20 08:24:30 test-server-1 cargo[117394]: #2903021619: cov: 2163 ft: 20854 corp: 2853 exec/s: 1464 oom/timeout/crash: 0/0/0 time: 56383s job: 6125 dft_time: 0";
+        let parsed = parse_fork_mode.parse(log).unwrap();
+        assert_eq!(
+            parsed,
+            Parsed {
+                cov: 2163,
+                ft: 20854,
+                corp: 2853,
+                corp_size: 0,
+                corp_size_unit: None,
+                lim: 0,
+                focus: None,
+                exec_s: 1464,
+                total_execs: 2903021619,
+                kind: None,
+                rss: 0,
+                oom: 0,
+                timeout: 0,
+                crash: 0,
+                time: 56383,
+                job: 6125,
+                dft_time: 0,
+                timestamp: Some(SyslogTime {
+                    month: 2,
+                    day: 20,
+                    hour: 8,
+                    minute: 24,
+                    second: 30
+                })
+            }
+        );
+
+        let log = "Feb 24 16:30:28 test-server-1 cargo[478967]: #190817895: cov: 400 ft: 7911 corp: 1901 exec/s 24015 oom/timeout/crash: 0/0/0 time: 252s job: 110 dft_time: 0";
+        let parsed = parse_fork_mode.parse(log).unwrap();
+        assert_eq!(
+            parsed,
+            Parsed {
+                cov: 400,
+                ft: 7911,
+                corp: 1901,
+                corp_size: 0,
+                corp_size_unit: None,
+                lim: 0,
+                focus: None,
+                exec_s: 24015,
+                total_execs: 190817895,
+                kind: None,
+                rss: 0,
+                oom: 0,
+                timeout: 0,
+                crash: 0,
+                time: 252,
+                job: 110,
+                dft_time: 0,
+                timestamp: Some(SyslogTime {
+                    month: 2,
+                    day: 24,
+                    hour: 16,
+                    minute: 30,
+                    second: 28
+                })
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_fork_mode_without_job() {
+        let log = "#190817895: cov: 400 ft: 7911 corp: 1901 exec/s 24015 oom/timeout/crash: 0/0/0 time: 252s";
+        let parsed = parse_fork_mode.parse(log).unwrap();
+        assert_eq!(parsed.time, 252);
+        assert_eq!(parsed.timestamp, None);
+        assert_eq!(parsed.job, 0);
+        assert_eq!(parsed.dft_time, 0);
+
+        let log = "#190817895: cov: 400 ft: 7911 corp: 1901 exec/s 24015 oom/timeout/crash: 0/0/0 time: 252s job: 110 dft_time: 17";
+        let parsed = parse_fork_mode.parse(log).unwrap();
+        assert_eq!(parsed.job, 110);
+        assert_eq!(parsed.dft_time, 17);
+
+        // only the message is matched, not whatever precedes it
+        let log = "Feb 24 16:30:28 cov: cargo[478967]: #190817895: cov: 400 ft: 7911 corp: 1901 exec/s 24015 oom/timeout/crash: 0/0/0 time: 252s";
+        assert_eq!(parse_fork_mode.parse(log).unwrap().cov, 400);
+        let log = "Feb 24 16:30:28 test-server-1 cargo[478967]: INFO: cov: 400 ft: 7911 corp: 1901 exec/s 24015 oom/timeout/crash: 0/0/0 time: 252s";
+        assert!(parse_fork_mode.parse(log).is_err());
+    }
+
+    #[test]
+    fn test_parse_job_mode() {
+        let log = "RELOAD cov: 641 ft: 9191 corp: 1640/591Kb lim: 2411 exec/s: 529 rss: 36Mb";
+        let parsed = parse_job_mode.parse(log).unwrap();
+        assert_eq!(
+            parsed,
+            Parsed {
+                cov: 641,
+                ft: 9191,
+                corp: 1640,
+                corp_size: 591 * 1024,
+                corp_size_unit: Some(SizeUnit::Kb),
+                lim: 2411,
+                focus: None,
+                exec_s: 529,
+                total_execs: 0,
+                kind: Some(StatKind::Reload),
+                rss: 36 * 1024 * 1024,
+                oom: 0,
+                timeout: 0,
+                crash: 0,
+                time: 0,
+                job: 0,
+                dft_time: 0,
+                timestamp: None
+            }
+        );
+
+        let log = "#2 INITED cov: 641 ft: 9191 corp: 1640/591Kb exec/s: 0";
+        let parsed = parse_job_mode.parse(log).unwrap();
+        assert_eq!(parsed.total_execs, 2);
+        assert_eq!(parsed.rss, 0);
+        assert_eq!(parsed.lim, 0);
+
+        let log = "#4096 NEW cov: 641 ft: 9191 corp: 1640/591Kb focus: 37 lim: 2411 exec/s: 529 rss: 36Mb L: 12/64 MS: 1 ChangeBit-";
+        let parsed = parse_job_mode.parse(log).unwrap();
+        assert_eq!(parsed.focus, Some(37));
+        assert_eq!(parsed.lim, 2411);
+        assert_eq!(parsed.rss, 36 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_parse_job_mode_kinds() {
+        let cases = [
+            (
+                "#2\tINITED cov: 12 ft: 13 corp: 1/1b exec/s: 0 rss: 30Mb",
+                StatKind::Inited,
+            ),
+            (
+                "#1234\tNEW    cov: 641 ft: 9191 corp: 1640/591Kb lim: 2411 exec/s: 529 rss: 36Mb L: 32/400 MS: 1 ChangeBit-",
+                StatKind::New,
+            ),
+            (
+                "#1235\tREDUCE cov: 641 ft: 9191 corp: 1640/590Kb lim: 2411 exec/s: 529 rss: 36Mb L: 31/400 MS: 2 EraseBytes-",
+                StatKind::Reduce,
+            ),
+            (
+                "#1048576\tpulse  cov: 641 ft: 9191 corp: 1640/590Kb lim: 2411 exec/s: 529 rss: 36Mb",
+                StatKind::Pulse,
+            ),
+            (
+                "#1048577\tDONE   cov: 641 ft: 9191 corp: 1640/590Kb lim: 2411 exec/s: 529 rss: 36Mb",
+                StatKind::Done,
+            ),
+        ];
+        for (log, kind) in cases {
+            let parsed = parse_job_mode.parse(log).unwrap();
+            assert_eq!(parsed.kind, Some(kind), "{log}");
+            assert_eq!(parsed.cov, if kind == StatKind::Inited { 12 } else { 641 });
+        }
+
+        let parsed = parse_job_mode.parse("#1234\tNEW    cov: 641 ft: 9191 corp: 1640/591Kb lim: 2411 exec/s: 529 rss: 36Mb L: 32/400 MS: 1 ChangeBit-").unwrap();
+        assert_eq!(parsed.total_execs, 1234);
+    }
+
+    #[test]
+    fn test_parse_job_mode_gb_corpus() {
+        let log = "#1000 pulse cov: 641 ft: 9191 corp: 120000/2Gb lim: 4096 exec/s: 529 rss: 1Gb";
+        let parsed = parse_job_mode.parse(log).unwrap();
+        assert_eq!(parsed.corp, 120000);
+        assert_eq!(parsed.corp_size, 2 * 1024 * 1024 * 1024);
+        assert_eq!(parsed.rss, 1024 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        let cases = [
+            ("591Kb", 591 * 1024),
+            ("512kb", 512 * 1024),
+            ("36Mb", 36 * 1024 * 1024),
+            ("2MB", 2 * 1024 * 1024),
+            ("1Gb", 1024 * 1024 * 1024),
+            ("17b", 17),
+            ("17B", 17),
+        ];
+        for (size, bytes) in cases {
+            assert_eq!(parse_size.parse(size).unwrap(), bytes, "{size}");
+        }
+        assert!(parse_size.parse("12").is_err());
+        assert!(parse_size.parse("99999999999Tb").is_err());
+        // a corrupt corpus size is left out, the rest of the line still counts
+        let parsed = parse_job_mode
+            .parse("#1024\tNEW    cov: 10 ft: 11 corp: 3/99999999999Tb exec/s: 0")
+            .unwrap();
+        assert_eq!((parsed.corp, parsed.corp_size), (3, 0));
+        assert_eq!(parsed.corp_size_unit, None);
+
+        let parsed = parse_job_mode
+            .parse("#1024\tNEW    cov: 10 ft: 11 corp: 3/512kb exec/s: 0 rss: 2MB")
+            .unwrap();
+        assert_eq!(parsed.corp_size, 512 * 1024);
+        assert_eq!(parsed.corp_size_unit, Some(SizeUnit::Kb));
+        assert_eq!(parsed.rss, 2 * 1024 * 1024);
+
+        let parsed = parse_job_mode
+            .parse("#2\tINITED cov: 3 ft: 3 corp: 1/123b exec/s: 0 rss: 30Mb")
+            .unwrap();
+        assert_eq!(parsed.corp_size, 123);
+        assert_eq!(parsed.corp_size_unit, Some(SizeUnit::B));
+    }
+
+    #[test]
+    fn test_classify_asan_report() {
+        let log = "\
+=================================================================
+==12345==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x55d1c0a3b2c0 bp 0x7ffc7d0e4a10 sp 0x7ffc7d0e4a08
+READ of size 1 at 0x602000000011 thread T0
+    #0 0x55d1c0a3b2c0 in parse_header src/parse.c:42:7
+    #1 0x55d1c0a3c1f4 in LLVMFuzzerTestOneInput fuzz/fuzz_parse.c:10:3
+    #2 0x55d1c09f5e33 in fuzzer::Fuzzer::ExecuteCallback(unsigned char const*, unsigned long) (/out/fuzz_parse+0x4fe33)
+
+0x602000000011 is located 0 bytes after 1-byte region [0x602000000010,0x602000000011)
+allocated by thread T0 here:
+    #0 0x55d1c0a0b6fd in malloc (/out/fuzz_parse+0x6f6fd)
+    #1 0x55d1c09f5d3a in fuzzer::Fuzzer::ExecuteCallback(unsigned char const*, unsigned long) (/out/fuzz_parse+0x4fd3a)
+
+SUMMARY: AddressSanitizer: heap-buffer-overflow src/parse.c:42:7 in parse_header
+Shadow bytes around the buggy address:
+  0x0c047fff7fb0: 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00
+=>0x0c047fff8000: fa fa[01]fa fa fa fa fa fa fa fa fa fa fa fa fa
+Shadow byte legend (one shadow byte represents 8 application bytes):
+  Addressable:           00
+  Partially addressable: 01 02 03 04 05 06 07
+  Heap left redzone:       fa
+==12345==ABORTING";
+        let mut classifier = LineClassifier::default();
+        for line in log.lines() {
+            assert_eq!(classifier.classify(line), Some(AuxLine::Report), "{line}");
+        }
+
+        let after = [
+            (
+                "MS: 1 ChangeBit-; base unit: adc83b19e793491b1c6ea0fd8b46cd9f32e592fc",
+                Some(AuxLine::Mutation),
+            ),
+            ("0x66,0x75,", Some(AuxLine::Mutation)),
+            ("fu", Some(AuxLine::Mutation)),
+            (
+                "artifact_prefix='./'; Test unit written to ./crash-da39a3ee",
+                Some(AuxLine::Report),
+            ),
+            ("Base64: ZnU=", Some(AuxLine::Report)),
+            ("READ of size 1 at 0x602000000011 thread T0", None),
+        ];
+        for (line, aux) in after {
+            assert_eq!(classifier.classify(line), aux, "{line}");
+        }
+
+        // a libFuzzer report has no `ABORTING`, the mutation ends it
+        let log = "\
+==117394== ERROR: libFuzzer: deadly signal
+    #0 0x55d1c6a9b1a1 in __sanitizer_print_stack_trace (/out/parse_header+0x1a31a1)
+NOTE: libFuzzer has rudimentary signal handlers.
+      Combine libFuzzer with AddressSanitizer or similar for better crash reports.
+SUMMARY: libFuzzer: deadly signal";
+        for line in log.lines() {
+            assert_eq!(classifier.classify(line), Some(AuxLine::Report), "{line}");
+        }
+        classifier.reset();
+        assert_eq!(classifier.classify("random text"), None);
+    }
+
+    #[tokio::test]
+    async fn test_parse_stream() {
+        let log = "\
+#2903021619: cov: 2163 ft: 20854 corp: 5491 exec/s 1410 oom/timeout/crash: 0/0/1 time: 3600s
+garbage
+#1024\tNEW    cov: 10 ft: 11 corp: 3/512Kb exec/s: 0 rss: 30Mb
+";
+        let parsed: Vec<_> = parse_stream(log.as_bytes()).collect().await;
+        let [Ok(fork), Err(StreamError::Unparsed(garbage)), Ok(job)] = parsed.as_slice() else {
+            panic!("unexpected {parsed:?}");
+        };
+        assert_eq!((fork.cov, fork.crash), (2163, 1));
+        assert_eq!(garbage, "garbage");
+        assert_eq!((job.cov, job.kind), (10, Some(StatKind::New)));
+    }
+
+    #[test]
+    fn test_crash_type_from_log() {
+        let cases = [
+            (
+                "==12345==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x55aa",
+                Some("heap-buffer-overflow"),
+            ),
+            (
+                "==12345==ERROR: AddressSanitizer: SEGV on unknown address 0x000000000000 (pc 0x55aa T0)",
+                Some("SEGV"),
+            ),
+            (
+                "==12345==ERROR: AddressSanitizer: attempting double-free on 0x602000000011 in thread T0:",
+                Some("attempting-double-free"),
+            ),
+            (
+                "==12345==ERROR: LeakSanitizer: detected memory leaks",
+                Some("detected-memory-leaks"),
+            ),
+            (
+                "==12345== ERROR: libFuzzer: deadly signal",
+                Some("deadly-signal"),
+            ),
+            ("==12345==ERROR: AddressSanitizer: 0x602000000011", None),
+            ("SUMMARY: AddressSanitizer: heap-buffer-overflow", None),
+        ];
+        for (log, crash_type) in cases {
+            assert_eq!(crash_type_from_log(log).as_deref(), crash_type, "{log}");
+        }
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let cases = [
+            ("==12345== ERROR: libFuzzer: deadly signal", Event::Crash),
+            (
+                "==12345==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011",
+                Event::Crash,
+            ),
+            (
+                "==12345== ERROR: libFuzzer: out-of-memory (used: 2050Mb; exceeds: 2048Mb)",
+                Event::Oom,
+            ),
+            (
+                "==12345== ERROR: libFuzzer: timeout after 1201 seconds",
+                Event::Timeout,
+            ),
+            ("INFO: Seed: 3918206239", Event::Restart),
+            ("Done 1000 runs in 2 second(s)", Event::Done),
+            (
+                "==12345== libFuzzer: out-of-memory (malloc(2147483648))",
+                Event::Oom,
+            ),
+            (
+                "==12345==libFuzzer: timeout after 25 seconds",
+                Event::Timeout,
+            ),
+        ];
+        for (log, event) in cases {
+            assert_eq!(parse_event.parse(log).unwrap(), event, "{log}");
+        }
+
+        assert!(
+            parse_event
+                .parse("SUMMARY: AddressSanitizer: heap-buffer-overflow")
+                .is_err()
+        );
+        assert!(parse_event.parse("Done 1000 runs").is_err());
+        assert!(
+            parse_event
+                .parse("SUMMARY: libFuzzer: out-of-memory")
+                .is_err()
+        );
+        assert!(
+            parse_event
+                .parse("RELOAD cov: 641 ft: 9191 corp: 1640/591Kb")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_aux_line() {
+        let cases = [
+            (
+                "\tNEW_FUNC[1/2]: 0x55d1c0a3b2c0 in parse_header src/parse.c:42",
+                AuxLine::NewFunc,
+            ),
+            (
+                "MS: 2 ChangeBit-CopyPart-; base unit: 9f2a6c1e4b0d8a7f",
+                AuxLine::Mutation,
+            ),
+            ("0x66,0x75,0x7a,0x7a,", AuxLine::Mutation),
+            ("\\x66uzz", AuxLine::Mutation),
+            (
+                "SUMMARY: AddressSanitizer: heap-buffer-overflow src/parse.c:42 in parse_header",
+                AuxLine::Report,
+            ),
+            (
+                "    #0 0x55d1c0a3b2c0 in parse_header src/parse.c:42:7",
+                AuxLine::Report,
+            ),
+            ("==12345==ABORTING", AuxLine::Report),
+            (
+                "artifact_prefix='./'; Test unit written to ./crash-da39a3ee",
+                AuxLine::Report,
+            ),
+            ("INFO: Seed: oops", AuxLine::Info),
+            ("stat::number_of_executed_units: 1000", AuxLine::Info),
+            ("Done 1000 runs in 2 second(s)", AuxLine::Info),
+        ];
+        for (log, aux) in cases {
+            assert_eq!(AuxLine::from_log(log).unwrap(), aux, "{log}");
+        }
+
+        assert!(AuxLine::from_log("#1234 NEW garbage").is_err());
+        assert!(AuxLine::from_log("random text").is_err());
+
+        assert_eq!(
+            rss_from_log("==1== ERROR: libFuzzer: out-of-memory (used: 2050Mb; exceeds: 2048Mb)"),
+            Some(2050 * 1024 * 1024)
+        );
+        assert_eq!(
+            rss_from_log("stat::peak_rss_mb:              38"),
+            Some(38 * 1024 * 1024)
+        );
+        assert_eq!(rss_from_log("stat::peak_rss_mb: 99999999999999"), None);
+        assert_eq!(rss_from_log("MS: 1 ChangeBit-;"), None);
+    }
+
+    #[test]
+    fn test_parse_artifact() {
+        let log = "artifact_prefix='./'; Test unit written to ./crash-da39a3ee5e6b4b0d3255bfef95601890afd80709";
+        assert_eq!(
+            Artifact::from_log(log).unwrap(),
+            Artifact {
+                kind: "crash".to_owned(),
+                hash: "da39a3ee5e6b4b0d3255bfef95601890afd80709".to_owned(),
+            }
+        );
+        let log = "artifact_prefix='/var/fuzz/out-1/'; Test unit written to /var/fuzz/out-1/slow-unit-da39a3ee5e6b4b0d3255bfef95601890afd80709";
+        assert_eq!(Artifact::from_log(log).unwrap().kind, "slow-unit");
+
+        assert!(Artifact::from_log("Test unit written to ./crash-notahash").is_err());
+        assert!(
+            Artifact::from_log(
+                "Test unit written to ./minimized-from-da39a3ee5e6b4b0d3255bfef95601890afd80709"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_target_from_log() {
+        let log = "INFO: Loaded 1 modules   (2163 inline 8-bit counters): 2163 [0x55aa, 0x55bb), (fuzz_target_1)";
+        assert_eq!(target_from_log(log), Some("fuzz_target_1"));
+        // the banner as libFuzzer prints it without a target
+        let log = "INFO: Loaded 1 modules   (2163 inline 8-bit counters): 2163 [0x55aa, 0x55bb), ";
+        assert_eq!(target_from_log(log), None);
+        assert_eq!(target_from_log("INFO: Loaded 1 modules (a b)"), None);
+        assert_eq!(target_from_log("INFO: Seed: 1234 (fuzz_target_1)"), None);
+    }
+
+    #[test]
+    fn test_syslog_time_age() {
+        use chrono::TimeZone;
+
+        let now = chrono::Local
+            .with_ymd_and_hms(2025, 1, 1, 0, 0, 10)
+            .unwrap();
+        let ts = SyslogTime {
+            month: 12,
+            day: 31,
+            hour: 23,
+            minute: 59,
+            second: 50,
+        };
+        assert_eq!(ts.age(now), Some(20));
+    }
+}
@@ -1,313 +1,4189 @@
+mod afl;
+mod http;
+mod idle;
+#[cfg(target_os = "linux")]
+mod journal;
+mod labels;
+mod manifest;
+mod otlp;
+#[cfg(any(test, feature = "native-histograms"))]
+mod protobuf;
+mod statsd;
+mod tail;
+
 use anyhow::Context;
-use std::path::Path;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
-use tokio::io::AsyncBufReadExt;
-use tokio::process::ChildStdout;
-use winnow::Result;
-use winnow::ascii::{dec_uint, space1};
-use winnow::combinator::{alt, opt, preceded, terminated};
-use winnow::error::{ContextError, ParseError};
-use winnow::token::{rest, take_until};
-
-use winnow::prelude::*;
+use futures::{FutureExt, Stream, StreamExt};
+use fuzz_exporter::{
+    Artifact, AuxLine, Event, LineClassifier, Parsed, SizeUnit, StatKind, StreamError,
+    crash_type_from_log, honggfuzz, rss_from_log, target_from_log,
+};
+use manifest::Manifest;
+use metrics::Recorder;
+use metrics_exporter_prometheus::{Matcher, NativeHistogramConfig, PrometheusHandle};
+use metrics_util::MetricKindMask;
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use metrics_util::layers::{PrefixLayer, Stack};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::LevelFilter;
+
+#[derive(clap::Parser)]
+#[command(version, about)]
+struct Args {
+    /// Logs of libFuzzer jobs to tail: files, directories with `*.log` files
+    /// or glob patterns like `'jobs/**/*.log'`, as many as needed. `-` reads
+    /// fork-mode output from stdin instead. Without any, fork-mode output is
+    /// read from journald on Linux, see `--journal-unit`.
+    #[arg(value_name = "PATH")]
+    paths: Vec<PathBuf>,
+
+    /// Read fork-mode output from stdin, same as passing `-`.
+    #[arg(long, conflicts_with = "paths")]
+    stdin: bool,
+
+    /// The logs in `DIR` are honggfuzz `--logfile`s rather than libFuzzer
+    /// job logs.
+    #[arg(long, requires = "paths", conflicts_with = "stdin")]
+    honggfuzz: bool,
+
+    /// TOML file naming the jobs and targets of the logs, read again on every
+    /// rescan. Logs it doesn't name are named after their files.
+    #[arg(long, value_name = "PATH", requires = "paths")]
+    manifest: Option<PathBuf>,
+
+    /// Only tail logs whose file name matches this regex.
+    #[arg(long, value_name = "REGEX", requires = "paths")]
+    include: Option<Regex>,
+
+    /// Skip logs whose file name matches this regex, like `^cargo-`.
+    #[arg(long, value_name = "REGEX", requires = "paths")]
+    exclude: Option<Regex>,
+
+    /// Split logs that interleave several fuzzers by a prefix at the start of
+    /// each line, like `^\[([^\]]+)\] `. The first group names the target,
+    /// every one is published as its own job and lines without the prefix
+    /// stay with the log's.
+    #[arg(long, value_name = "REGEX", requires = "paths", value_parser = parse_target_prefix_regex)]
+    target_prefix_regex: Option<Regex>,
+
+    /// AFL++ output directory to read `fuzzer_stats` from, instead of
+    /// libFuzzer logs.
+    #[arg(long, value_name = "OUT_DIR", conflicts_with_all = ["paths", "stdin"])]
+    afl: Option<PathBuf>,
+
+    /// Print what the parsers make of every line of a captured log and exit,
+    /// with an error if any line wasn't recognized.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["paths", "stdin", "afl"])]
+    parse_check: Option<PathBuf>,
+
+    /// Read the logs to the end once, print the metrics as JSON and exit,
+    /// instead of tailing them.
+    #[arg(long, requires = "paths", conflicts_with_all = ["stdin", "afl", "parse_check"])]
+    once: bool,
+
+    /// How often to publish job metrics and re-read AFL++ stats, in seconds.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    interval_secs: u64,
+
+    /// Leave jobs that didn't update for this many seconds out of the
+    /// aggregates, as if they were gone. 0 keeps them in.
+    #[arg(long, value_name = "SECS", default_value_t = 0)]
+    stale_after: u64,
+
+    /// How to aggregate a metric across jobs instead of its default, like
+    /// `cov=avg`, by its name without the prefix. Can be repeated.
+    #[arg(long, value_name = "METRIC=max|min|sum|avg", value_parser = parse_aggregate)]
+    aggregate: Vec<(String, Aggregation)>,
+
+    /// Only apply every Nth stat line of a job log, the others just count as
+    /// activity. For fast targets that print many lines per interval, event
+    /// lines like crashes are never skipped.
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "paths", value_parser = clap::value_parser!(u64).range(1..))]
+    sample_rate: u64,
+
+    /// How long to wait for a log that doesn't exist yet before giving up on
+    /// it until the next rescan, in seconds.
+    #[arg(long, default_value_t = 60)]
+    log_wait_secs: u64,
+
+    /// How often to look for new or removed log files, in seconds.
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    rescan_secs: u64,
+
+    /// Count the files of the job's corpus directory every interval, as
+    /// `corp_files` and `corp_bytes`, to check `corp:` against. `JOB=DIR` is
+    /// the corpus of one job, a plain `DIR` holds the corpora of several,
+    /// named after their jobs or targets. Can be repeated.
+    #[arg(long, value_name = "[JOB=]DIR", value_parser = parse_corpus_dir)]
+    corpus_dir: Vec<(Option<String>, PathBuf)>,
+
+    /// Also record every parsed exec/s value into the `exec_s_hist`
+    /// histogram, exported as a summary unless `--exec-s-buckets` is set.
+    #[arg(long)]
+    exec_s_histogram: bool,
+
+    /// Comma-separated bucket bounds for the exec/s histogram.
+    #[arg(long, value_delimiter = ',', requires = "exec_s_histogram")]
+    exec_s_buckets: Vec<f64>,
+
+    /// Export the exec/s histogram as a Prometheus native histogram, with
+    /// exponential buckets instead of predefined ones. It's only in the
+    /// protobuf format, which Prometheus asks for once native histograms are
+    /// enabled, so text scrapes and pushes leave it out. Needs a build with
+    /// the `native-histograms` feature.
+    #[arg(long, requires = "exec_s_histogram", conflicts_with = "exec_s_buckets")]
+    exec_s_native_histogram: bool,
+
+    /// Also export every field of each job's latest stat line as is, as
+    /// `line_field{field="dft_time"}` and so on, to check what the parser
+    /// makes of a target's log. That's 14 more series per job: meant for
+    /// debugging a few jobs, not for scraping a whole fleet.
+    #[arg(long)]
+    verbose_metrics: bool,
+
+    /// systemd unit to follow in journalctl mode, can be repeated.
+    #[cfg(target_os = "linux")]
+    #[arg(long, default_value = "fuzz", value_parser = clap::builder::NonEmptyStringValueParser::new())]
+    journal_unit: Vec<String>,
+
+    /// Extra journalctl match like `_HOSTNAME=fuzz-1`, can be repeated.
+    #[cfg(target_os = "linux")]
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_journal_match)]
+    journal_match: Vec<String>,
+
+    /// Follow a system service instead of a user one.
+    #[cfg(target_os = "linux")]
+    #[arg(long, conflicts_with = "user")]
+    system: bool,
+
+    /// Follow a user service (default).
+    #[cfg(target_os = "linux")]
+    #[arg(long)]
+    user: bool,
+
+    /// Where metrics go, scraped from `--listen` or pushed to StatsD or an
+    /// OpenTelemetry collector.
+    #[arg(long, value_enum, default_value_t = Exporter::Prometheus)]
+    exporter: Exporter,
+
+    /// StatsD host for `--exporter statsd`.
+    #[arg(long, default_value = "127.0.0.1")]
+    statsd_host: String,
+
+    /// StatsD port for `--exporter statsd`.
+    #[arg(long, default_value_t = 8125)]
+    statsd_port: u16,
+
+    /// OTLP/HTTP metrics endpoint of the collector for `--exporter otlp`.
+    #[arg(long, default_value = "http://localhost:4318/v1/metrics")]
+    otlp_endpoint: String,
+
+    /// How often to export to `--otlp-endpoint`, in seconds.
+    #[arg(long, default_value_t = 15, value_parser = clap::value_parser!(u64).range(1..))]
+    otlp_interval_secs: u64,
+
+    /// Address to serve Prometheus metrics and `/healthz` on, only the
+    /// latter with the other exporters.
+    #[arg(long, env = "FUZZ_EXPORTER_LISTEN", default_value = "0.0.0.0:9000")]
+    listen: SocketAddr,
+
+    /// Serve on this Unix domain socket instead of `--listen`, e.g. for a
+    /// sidecar on a shared volume. It's removed on shutdown.
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH")]
+    unix_socket: Option<PathBuf>,
+
+    /// PEM certificate chain to serve over HTTPS with, instead of HTTP.
+    #[arg(long, value_name = "PATH", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key of `--tls-cert`.
+    #[arg(long, value_name = "PATH", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Only serve the metrics to requests with this `Authorization: Bearer`
+    /// token, `/healthz` stays open.
+    #[arg(long, env = "FUZZ_EXPORTER_AUTH_TOKEN", hide_env_values = true, value_parser = clap::builder::NonEmptyStringValueParser::new())]
+    auth_token: Option<String>,
+
+    /// Also push the metrics to this Prometheus Pushgateway, like
+    /// `http://pushgateway:9091`, for hosts Prometheus can't scrape. Their
+    /// `job` label becomes `exported_job`, scraped or pushed.
+    #[arg(long, value_name = "URL")]
+    pushgateway: Option<String>,
+
+    /// `job` label the pushed metrics are grouped under.
+    #[arg(long, default_value = "fuzz-exporter", value_parser = parse_grouping_value)]
+    push_job: String,
+
+    /// Extra grouping label for the pushed metrics, like `host=fuzz-1`, can
+    /// be repeated.
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_grouping_label)]
+    push_label: Vec<(String, String)>,
+
+    /// How often to push to `--pushgateway`, in seconds.
+    #[arg(long, default_value_t = 15, value_parser = clap::value_parser!(u64).range(1..))]
+    push_interval_secs: u64,
+
+    /// `/healthz` fails once no stat line was parsed for this many seconds.
+    #[arg(long, default_value_t = 300)]
+    health_max_age_secs: u64,
+
+    /// Prefix prepended to every metric name as `<prefix>_<name>`.
+    /// Must match `[a-zA-Z_][a-zA-Z0-9_]*`.
+    #[arg(long, default_value = "fuzz", value_parser = parse_metric_prefix)]
+    metric_prefix: String,
+
+    /// `host` label added to every metric, so it's kept however the exporter
+    /// is scraped. Defaults to the hostname.
+    #[arg(long, value_name = "HOST", default_value_t = hostname(), value_parser = clap::builder::NonEmptyStringValueParser::new())]
+    host_label: String,
+
+    /// Leave out the `host` label.
+    #[arg(long, conflicts_with = "host_label")]
+    no_host_label: bool,
+
+    /// Static label added to every metric, like `region=eu-west`, can be
+    /// repeated. Keys must be unique and not one the exporter sets itself.
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_static_label)]
+    label: Vec<(String, String)>,
+
+    /// TOML file with defaults for the other options, keyed by their long
+    /// names like `interval-secs = 5`, `paths` for the logs. Options given on
+    /// the command line or through the environment win.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Only log warnings and errors, unless `RUST_LOG` is set. Logs go to
+    /// stderr either way.
+    #[arg(long, short)]
+    quiet: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Exporter {
+    Prometheus,
+    Statsd,
+    Otlp,
+}
+
+/// How the unlabeled series of a gauge is computed from the jobs'.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum Aggregation {
+    Max,
+    Min,
+    Sum,
+    Avg,
+}
+
+impl Aggregation {
+    fn apply(self, values: &[f64]) -> f64 {
+        match self {
+            Aggregation::Max => values.iter().copied().reduce(f64::max).unwrap_or(0.0),
+            Aggregation::Min => values.iter().copied().reduce(f64::min).unwrap_or(0.0),
+            Aggregation::Sum => values.iter().sum(),
+            Aggregation::Avg if values.is_empty() => 0.0,
+            Aggregation::Avg => values.iter().sum::<f64>() / values.len() as f64,
+        }
+    }
+}
+
+impl Args {
+    fn reads_stdin(&self) -> bool {
+        self.stdin || matches!(self.paths.as_slice(), [path] if path == Path::new("-"))
+    }
+
+    fn load_manifest(&self) -> anyhow::Result<Manifest> {
+        self.manifest
+            .as_deref()
+            .map_or_else(|| Ok(Manifest::default()), Manifest::load)
+    }
+
+    /// Whether `--include` and `--exclude` let the log through.
+    fn tails(&self, log: &Path) -> bool {
+        let name = log.file_name().unwrap_or_default().to_string_lossy();
+        self.include.as_ref().is_none_or(|re| re.is_match(&name))
+            && !self.exclude.as_ref().is_some_and(|re| re.is_match(&name))
+    }
+
+    /// Input mode, as reported in the build info metric.
+    fn mode(&self) -> &'static str {
+        if self.afl.is_some() {
+            "afl"
+        } else if self.reads_stdin() {
+            "stdin"
+        } else if self.honggfuzz {
+            "honggfuzz"
+        } else if !self.paths.is_empty() {
+            "jobs"
+        } else {
+            "journalctl"
+        }
+    }
+}
+
+fn hostname() -> String {
+    gethostname::gethostname().to_string_lossy().into_owned()
+}
+
+fn parse_metric_prefix(prefix: &str) -> Result<String, String> {
+    let mut chars = prefix.chars();
+    let valid_start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    if valid_start && chars.all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        Ok(prefix.to_owned())
+    } else {
+        Err("must match [a-zA-Z_][a-zA-Z0-9_]*".to_owned())
+    }
+}
+
+fn parse_grouping_value(value: &str) -> Result<String, String> {
+    if value.is_empty() || value.contains('/') {
+        return Err("must be non-empty and without `/`".to_owned());
+    }
+    Ok(value.to_owned())
+}
+
+fn parse_grouping_label(label: &str) -> Result<(String, String), String> {
+    let (name, value) = label.split_once('=').ok_or("must be KEY=VALUE")?;
+    let name = parse_metric_prefix(name)?;
+    Ok((name, parse_grouping_value(value)?))
+}
+
+fn parse_static_label(label: &str) -> Result<(String, String), String> {
+    let (name, value) = label.split_once('=').ok_or("must be KEY=VALUE")?;
+    let name = parse_metric_prefix(name)?;
+    if name.starts_with("__") {
+        return Err("label names starting with `__` are reserved".to_owned());
+    }
+    Ok((name, value.to_owned()))
+}
+
+fn parse_corpus_dir(value: &str) -> Result<(Option<String>, PathBuf), String> {
+    match value.split_once('=') {
+        Some((job, dir)) if !job.is_empty() && !dir.is_empty() => {
+            Ok((Some(job.to_owned()), dir.into()))
+        }
+        Some(_) => Err("must be JOB=DIR or DIR".to_owned()),
+        None => Ok((None, value.into())),
+    }
+}
+
+fn parse_aggregate(aggregate: &str) -> Result<(String, Aggregation), String> {
+    let (metric, aggregation) = aggregate
+        .split_once('=')
+        .ok_or("must be METRIC=AGGREGATION")?;
+    let aggregation = <Aggregation as clap::ValueEnum>::from_str(aggregation, true)?;
+    Ok((parse_metric_prefix(metric)?, aggregation))
+}
+
+/// The Pushgateway URL of the `--push-job`/`--push-label` group.
+fn push_url(args: &Args, base: &str) -> anyhow::Result<hyper::Uri> {
+    let mut url = format!(
+        "{}/metrics/job/{}",
+        base.trim_end_matches('/'),
+        args.push_job
+    );
+    for (name, value) in &args.push_label {
+        url.push_str(&format!("/{name}/{value}"));
+    }
+    url.parse()
+        .with_context(|| format!("invalid --pushgateway URL {url}"))
+}
+
+fn parse_target_prefix_regex(prefix: &str) -> Result<Regex, String> {
+    let regex = Regex::new(prefix).map_err(|e| e.to_string())?;
+    if regex.captures_len() < 2 {
+        return Err("must have a group for the target".to_owned());
+    }
+    Ok(regex)
+}
+
+#[cfg(target_os = "linux")]
+fn parse_journal_match(field_match: &str) -> Result<String, String> {
+    match field_match.split_once('=') {
+        Some((field, _)) if !field.is_empty() => Ok(field_match.to_owned()),
+        _ => Err("must be FIELD=VALUE".to_owned()),
+    }
+}
+
+/// Parses the command line, with the options it doesn't set taken from
+/// `--config`.
+fn parse_args(argv: Vec<OsString>) -> anyhow::Result<Args> {
+    use clap::parser::ValueSource;
+
+    let command = <Args as clap::CommandFactory>::command();
+    // `requires` may only be met with the file, errors are left to the
+    // final parse
+    let Ok(matches) = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(&argv)
+    else {
+        return Ok(clap::Parser::try_parse_from(argv)?);
+    };
+    let Some(path) = matches.get_one::<PathBuf>("config") else {
+        return Ok(clap::Parser::try_parse_from(argv)?);
+    };
+    let config = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let config: toml::Table =
+        toml::from_str(&config).with_context(|| format!("invalid config {}", path.display()))?;
+
+    let mut config_argv = Vec::new();
+    for (key, value) in config {
+        let arg = command
+            .get_arguments()
+            .filter(|arg| !matches!(arg.get_id().as_str(), "config" | "help" | "version"))
+            .find(|arg| arg.get_long().unwrap_or(arg.get_id().as_str()) == key)
+            .with_context(|| format!("unknown key `{key}` in {}", path.display()))?;
+        if matches!(
+            matches.value_source(arg.get_id().as_str()),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        ) {
+            continue;
+        }
+        config_arg(arg, value, &mut config_argv)
+            .with_context(|| format!("invalid `{key}` in {}", path.display()))?;
+    }
+
+    let mut argv = argv.into_iter();
+    let argv = argv.next().into_iter().chain(config_argv).chain(argv);
+    Ok(clap::Parser::try_parse_from(argv)?)
+}
+
+/// Appends `value` as the command line arguments of `arg`.
+fn config_arg(arg: &clap::Arg, value: toml::Value, argv: &mut Vec<OsString>) -> anyhow::Result<()> {
+    let flag = |value: String| match arg.get_long() {
+        Some(long) => format!("--{long}={value}").into(),
+        None => value.into(),
+    };
+    match value {
+        toml::Value::Boolean(set) if !arg.get_action().takes_values() => {
+            if set {
+                argv.push(format!("--{}", arg.get_long().unwrap_or_default()).into());
+            }
+        }
+        _ if !arg.get_action().takes_values() => anyhow::bail!("must be true or false"),
+        toml::Value::String(value) => argv.push(flag(value)),
+        toml::Value::Integer(value) => argv.push(flag(value.to_string())),
+        toml::Value::Float(value) => argv.push(flag(value.to_string())),
+        toml::Value::Array(values) => {
+            for value in values {
+                if value.is_array() {
+                    anyhow::bail!("must not have nested arrays");
+                }
+                config_arg(arg, value, argv)?;
+            }
+        }
+        _ => anyhow::bail!("must be a string, a number or an array of them"),
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = parse_args(std::env::args_os().collect());
+    // logs go to stderr, stdout is for `--parse-check` and `--once`
+    let level = match &args {
+        Ok(args) if args.quiet => LevelFilter::WARN,
+        _ => LevelFilter::INFO,
+    };
+    let filter = EnvFilter::builder()
+        .with_default_directive(level.into())
+        .from_env_lossy();
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .init();
+
+    let args = match args {
+        Ok(args) => Arc::new(args),
+        Err(e) => match e.downcast::<clap::Error>() {
+            Ok(e) => e.exit(),
+            Err(e) => {
+                tracing::error!("{e:?}");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+    let res = match &args.parse_check {
+        Some(path) => parse_check(path),
+        None if args.once => summarize_logs(&args),
+        None => start(&args).await,
+    };
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            tracing::error!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn start(args: &Arc<Args>) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    let listener = match &args.unix_socket {
+        Some(path) => http::Listener::unix(path.clone())?,
+        None => http::Listener::tcp(args.listen).await?,
+    };
+    #[cfg(not(unix))]
+    let listener = http::Listener::tcp(args.listen).await?;
+    let tls = match (&args.tls_cert, &args.tls_key) {
+        (Some(cert), Some(key)) => Some(http::tls_acceptor(cert, key)?),
+        _ => None,
+    };
+    let max_age = args.health_max_age_secs;
+    let is_healthy = Arc::new(move || {
+        let last_update = LAST_UPDATE.load(Ordering::Relaxed);
+        last_update != 0 && unix_secs().saturating_sub(last_update) <= max_age
+    });
+
+    let mut meter_provider = None;
+    let mut protobuf = None;
+    let render = match args.exporter {
+        Exporter::Prometheus => {
+            let mut builder = metrics_exporter_prometheus::PrometheusBuilder::new();
+            if !args.exec_s_buckets.is_empty() {
+                builder = builder
+                    .set_buckets_for_metric(
+                        Matcher::Suffix("exec_s_hist".into()),
+                        &args.exec_s_buckets,
+                    )
+                    .context("invalid --exec-s-buckets")?;
+            }
+            if args.exec_s_native_histogram {
+                anyhow::ensure!(
+                    cfg!(feature = "native-histograms"),
+                    "--exec-s-native-histogram needs a build with the native-histograms feature"
+                );
+                // about 9% wide buckets, at most 160 of them per job
+                let config =
+                    NativeHistogramConfig::new(1.09, 160, 0.0).map_err(|e| anyhow::anyhow!(e))?;
+                builder = builder
+                    .set_native_histogram_for_metric(Matcher::Suffix("exec_s_hist".into()), config);
+            }
+            let recorder = builder
+                .idle_timeout(MetricKindMask::ALL, Some(idle::IDLE_TIMEOUT))
+                .build_recorder();
+            let handle = recorder.handle();
+            let (recorder, series) = idle::KeepFresh::new(recorder);
+            let label = job_label(args);
+            protobuf = render_protobuf(args, &handle, &series);
+            let upkeep = handle.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    upkeep.run_upkeep();
+                }
+            });
+            install_recorder(recorder, args)?;
+            let render: http::Render = Arc::new(move || {
+                without_removed_jobs(&series, label, |removed| {
+                    strip_jobs(&handle.render(), label, removed)
+                })
+            });
+            if let Some(base) = &args.pushgateway {
+                let url = push_url(args, base)?;
+                let interval = Duration::from_secs(args.push_interval_secs);
+                tokio::spawn(http::push(url, render.clone(), interval));
+            }
+            Some(render)
+        }
+        Exporter::Statsd => {
+            anyhow::ensure!(
+                args.pushgateway.is_none(),
+                "--pushgateway needs --exporter prometheus"
+            );
+            let recorder =
+                metrics_exporter_statsd::StatsdBuilder::from(&args.statsd_host, args.statsd_port)
+                    .build(None)
+                    .context("failed to set up the StatsD client")?;
+            let job_totals = matches!(args.mode(), "jobs" | "honggfuzz" | "afl");
+            let recorder = statsd::AbsoluteCounters::new(recorder, job_totals);
+            install_recorder(recorder, args)?;
+            let _ = EVICT_JOB.set(Box::new(|_| {}));
+            None
+        }
+        Exporter::Otlp => {
+            anyhow::ensure!(
+                args.pushgateway.is_none(),
+                "--pushgateway needs --exporter prometheus"
+            );
+            let interval = Duration::from_secs(args.otlp_interval_secs);
+            let provider = otlp::meter_provider(&args.otlp_endpoint, interval)
+                .context("failed to set up the OTLP exporter")?;
+            let recorder = otlp::OtlpRecorder::new(&provider);
+            let evictor = recorder.evictor();
+            let label = job_label(args);
+            let _ = EVICT_JOB.set(Box::new(move |job| {
+                evictor.evict(|key| job_of(key, label) == Some(job));
+            }));
+            install_recorder(recorder, args)?;
+            meter_provider = Some(provider);
+            None
+        }
+    };
+    describe_metrics();
+    tracing::info!(mode = args.mode(), listen = %listener, tls = tls.is_some(), "starting server");
+    let token = args.auth_token.as_deref().map(Arc::from);
+    tokio::spawn(http::serve(
+        listener, tls, token, render, protobuf, is_healthy,
+    ));
+
+    metrics::gauge!(
+        "exporter_build_info",
+        "version" => env!("CARGO_PKG_VERSION"),
+        "mode" => args.mode(),
+    )
+    .set(1);
+
+    // Dropping the parser stops its tailing tasks and kills journalctl.
+    let res = tokio::select! {
+        res = run(args) => res,
+        res = shutdown_signal() => {
+            tracing::info!("shutting down");
+            res
+        }
+    };
+    if let Some(provider) = meter_provider {
+        // exports what's left, blocking on the collector
+        let shutdown = tokio::task::spawn_blocking(move || provider.shutdown()).await?;
+        if let Err(e) = shutdown {
+            tracing::warn!("failed to export the last metrics: {e}");
+        }
+    }
+    res
+}
+
+/// Labels the exporter sets on some series itself, `le` and `quantile`
+/// come from the histograms.
+const EXPORTER_LABELS: [&str; 13] = [
+    "job",
+    "exported_job",
+    "target",
+    "type",
+    "kind",
+    "hash",
+    "unit",
+    "format",
+    "field",
+    "version",
+    "mode",
+    "le",
+    "quantile",
+];
+
+/// The `--host-label` and `--label` labels of every metric.
+fn global_labels(args: &Args) -> anyhow::Result<Vec<metrics::Label>> {
+    let mut labels = Vec::new();
+    if !args.no_host_label {
+        labels.push(metrics::Label::new("host", args.host_label.clone()));
+    }
+    for (name, value) in &args.label {
+        anyhow::ensure!(
+            !EXPORTER_LABELS.contains(&name.as_str()),
+            "--label {name} clashes with the exporter's own `{name}` label"
+        );
+        anyhow::ensure!(
+            labels.iter().all(|label| label.key() != name),
+            "--label {name} is given twice, or clashes with --host-label"
+        );
+        labels.push(metrics::Label::new(name.clone(), value.clone()));
+    }
+    Ok(labels)
+}
+
+/// The label the job of a series is exported as.
+fn job_label(args: &Args) -> &'static str {
+    if args.pushgateway.is_some() {
+        "exported_job"
+    } else {
+        "job"
+    }
+}
+
+fn install_recorder(recorder: impl Recorder + Sync + 'static, args: &Args) -> anyhow::Result<()> {
+    let labels = global_labels(args)?;
+    // Metrics are registered without a prefix, e.g. `cov`, and exported as
+    // `<prefix>.cov`, which Prometheus renders as `<prefix>_cov`.
+    let recorder = labels::GlobalLabels::new(recorder, labels);
+    Stack::new(labels::JobLabel::new(recorder, job_label(args)))
+        .push(PrefixLayer::new(&args.metric_prefix))
+        .install()
+        .map_err(|_| anyhow::anyhow!("failed to install the metrics recorder"))
+}
+
+/// Native histograms are only in the protobuf format.
+#[cfg(feature = "native-histograms")]
+fn render_protobuf(
+    args: &Args,
+    handle: &PrometheusHandle,
+    series: &idle::Series,
+) -> Option<http::RenderProtobuf> {
+    let (handle, series) = (handle.clone(), series.clone());
+    let label = job_label(args);
+    args.exec_s_native_histogram.then(|| {
+        Arc::new(move || {
+            without_removed_jobs(&series, label, |removed| {
+                protobuf::strip_jobs(&handle.render_protobuf(), label, removed)
+            })
+        }) as http::RenderProtobuf
+    })
+}
+
+#[cfg(not(feature = "native-histograms"))]
+fn render_protobuf(
+    _: &Args,
+    _: &PrometheusHandle,
+    _: &idle::Series,
+) -> Option<http::RenderProtobuf> {
+    None
+}
+
+/// `# HELP` lines and units for every metric the exporter publishes.
+fn describe_metrics() {
+    use metrics::{Unit, describe_counter, describe_gauge, describe_histogram};
+
+    describe_gauge!(
+        "exporter_build_info",
+        "Exporter version and input mode, always 1."
+    );
+    describe_gauge!("cov", "Code coverage, covered edges.");
+    describe_gauge!("cov_min", "Lowest coverage of the jobs that printed stats.");
+    describe_gauge!("cov_peak", "Highest coverage seen, kept across restarts.");
+    describe_gauge!("feat", "Coverage features (ft).");
+    describe_gauge!("ft_per_cov", "Features per covered edge.");
+    describe_gauge!("corp", "Inputs in the corpus.");
+    describe_gauge!("corp_total", "Inputs in the corpora of all jobs.");
+    describe_gauge!("corp_rate", "Corpus growth in inputs per minute.");
+    describe_gauge!("corp_size", Unit::Bytes, "Size of the corpus.");
+    describe_gauge!(
+        "corp_size_total",
+        Unit::Bytes,
+        "Size of the corpora of all jobs."
+    );
+    describe_gauge!(
+        "corp_files",
+        "Files in the corpus directory, with --corpus-dir."
+    );
+    describe_gauge!(
+        "corp_bytes",
+        Unit::Bytes,
+        "Size of the files in the corpus directory, with --corpus-dir."
+    );
+    describe_gauge!(
+        "corp_size_unit_info",
+        "1 for the unit libFuzzer prints the corpus size in."
+    );
+    describe_gauge!("lim", Unit::Bytes, "Current input length limit.");
+    describe_gauge!("exec_s", "Executions per second, averaged over the run.");
+    describe_gauge!("exec_s_avg", "Executions per second, per job on average.");
+    describe_gauge!(
+        "exec_s_instant",
+        "Executions per second between the latest stat lines."
+    );
+    describe_histogram!("exec_s_hist", "Executions per second of every stat line.");
+    describe_gauge!(
+        "line_field",
+        "A field of the latest stat line as parsed, with --verbose-metrics."
+    );
+    describe_gauge!("rss", Unit::Bytes, "Resident memory of the fuzzer.");
+    describe_counter!("oom", "Out-of-memory errors.");
+    describe_counter!("timeout", "Timeouts.");
+    describe_counter!("crash", "Crashes, also by the type of their report.");
+    describe_gauge!(
+        "last_crash_timestamp",
+        "Unix time the last crash was counted, jobs that didn't crash have no series."
+    );
+    describe_counter!(
+        "incidents_total",
+        "Out-of-memory errors, timeouts and crashes together."
+    );
+    describe_counter!("artifacts_total", "Crash, timeout and oom inputs written.");
+    describe_gauge!("time", Unit::Seconds, "Run time of the fork-mode fuzzer.");
+    describe_counter!(
+        "time_seconds_total",
+        Unit::Seconds,
+        "Run time of the fork-mode fuzzer, reset when it restarts."
+    );
+    describe_gauge!("job", "Fork-mode child jobs started.");
+    describe_gauge!(
+        "dft_time",
+        Unit::Seconds,
+        "Time spent collecting data-flow traces."
+    );
+    describe_gauge!(
+        "corp_avg_input_bytes",
+        Unit::Bytes,
+        "Average size of a corpus input, only known in job mode."
+    );
+    describe_gauge!(
+        "focus_hit_fraction",
+        "Share of the corpus reaching the -focus_function, only with one set."
+    );
+    describe_gauge!(
+        "cov_per_mexec",
+        "Covered edges per million executions, falls with diminishing returns."
+    );
+    describe_gauge!(
+        "dft_fraction",
+        "Share of the fork-mode run time spent collecting data-flow traces."
+    );
+    describe_counter!("execs_total", "Fuzz target executions.");
+    describe_counter!("new_total", "Inputs added to the corpus for new coverage.");
+    describe_counter!("new_funcs_total", "Functions reached for the first time.");
+    describe_counter!("restarts_total", "Fuzzer (re)starts.");
+    describe_counter!("lines_total", "Log lines read.");
+    describe_counter!(
+        "stat_lines_total",
+        "Stat lines read, by the format they parsed as."
+    );
+    describe_gauge!(
+        "line_format_info",
+        "1 for the format of the latest stat line."
+    );
+    describe_counter!(
+        "aux_lines_total",
+        "Lines other than stats and events, by kind."
+    );
+    describe_counter!("parse_errors_total", "Log lines that failed to parse.");
+    describe_counter!("jobs_total", "Job logs discovered.");
+    describe_gauge!("jobs_active", "Jobs that updated within --stale-after.");
+    describe_gauge!("job_done", "Whether the bounded run finished.");
+    describe_gauge!(
+        "seconds_since_update",
+        Unit::Seconds,
+        "Time since the last stat line."
+    );
+    describe_gauge!(
+        "seconds_since_cov_increase",
+        Unit::Seconds,
+        "Time since coverage last went up."
+    );
+    describe_gauge!(
+        "log_lag_seconds",
+        Unit::Seconds,
+        "Delay between logging a line and reading it."
+    );
+    describe_counter!(
+        "journal_restarts_total",
+        "journalctl restarts after it exited."
+    );
+    describe_counter!(
+        "task_panics_total",
+        "Log tailing tasks restarted after a panic."
+    );
+    describe_gauge!(
+        "afl_bitmap_cvg",
+        Unit::Percent,
+        "AFL++ edge coverage of the bitmap."
+    );
+    describe_gauge!("afl_stability", Unit::Percent, "AFL++ stability.");
+    describe_gauge!("afl_cycles_done", "AFL++ queue cycles completed.");
+    describe_gauge!("afl_pending_favs", "AFL++ favored inputs not fuzzed yet.");
+    describe_gauge!("afl_pending_total", "AFL++ inputs not fuzzed yet.");
+    describe_counter!("afl_execs_done", "AFL++ fuzz target executions.");
+}
+
+async fn run(args: &Arc<Args>) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        args.corpus_dir.is_empty() || matches!(args.mode(), "jobs" | "honggfuzz"),
+        "--corpus-dir needs job logs to tail"
+    );
+    if let Some(out_dir) = &args.afl {
+        return afl::afl_parser(args, out_dir).await;
+    }
+    if args.reads_stdin() {
+        return stdin_parser(args).await;
+    }
+    if args.paths.is_empty() {
+        #[cfg(target_os = "linux")]
+        return journal::journalctl_parser(args).await;
+        #[cfg(not(target_os = "linux"))]
+        anyhow::bail!("journalctl mode is Linux only, pass the logs to tail");
+    }
+    jobs_parser(args, &args.paths).await
+}
+
+fn parse_check(path: &Path) -> anyhow::Result<()> {
+    use std::io::BufRead;
+
+    let file =
+        std::fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut failed = 0;
+    let mut classifier = LineClassifier::default();
+    for (i, line) in std::io::BufReader::new(file).split(b'\n').enumerate() {
+        let line = line.with_context(|| format!("failed to read {}", path.display()))?;
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end_matches('\r');
+        let n = i + 1;
+
+        let stats = Parsed::from_log(line).or_else(|fork_err| {
+            // report whichever parser got further
+            Parsed::from_log_job(line).map_err(|job_err| {
+                if job_err.offset() > fork_err.offset() {
+                    job_err
+                } else {
+                    fork_err
+                }
+            })
+        });
+        match stats {
+            Ok(parsed) => {
+                classifier.reset();
+                println!("{n}: {parsed:?}");
+            }
+            Err(e) => {
+                let aux = classifier.classify(line);
+                if let Ok(event) = Event::from_log(line) {
+                    println!("{n}: {event:?}");
+                } else if let Some(aux) = aux {
+                    println!("{n}: {aux:?}");
+                } else {
+                    failed += 1;
+                    println!("{n}: failed to parse\n{e}");
+                }
+            }
+        }
+    }
+
+    anyhow::ensure!(failed == 0, "{failed} lines failed to parse");
+    Ok(())
+}
+
+/// Resolves on Ctrl-C or SIGTERM.
+async fn shutdown_signal() -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut sigterm = signal(SignalKind::terminate())?;
+        tokio::select! {
+            res = tokio::signal::ctrl_c() => res?,
+            _ = sigterm.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await?;
+
+    Ok(())
+}
+
+/// Every SIGUSR1, which asks for a dump of the jobs.
+fn dump_requests() -> anyhow::Result<impl Stream<Item = ()>> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let signal = signal(SignalKind::user_defined1())?;
+        Ok(futures::stream::unfold(signal, |mut signal| async move {
+            signal.recv().await?;
+            Some(((), signal))
+        }))
+    }
+    #[cfg(not(unix))]
+    Ok(futures::stream::pending())
+}
+/// Unix time of the last stat line parsed in any mode, 0 if there was none yet.
+static LAST_UPDATE: AtomicU64 = AtomicU64::new(0);
+
+/// Job logs and split-off targets found so far, published as `jobs_total`
+/// with the other job metrics.
+static JOBS_FOUND: AtomicU64 = AtomicU64::new(0);
+
+/// Jobs whose log is gone. Their stale values would be misleading, so every
+/// exporter leaves them out: absent rather than zero, as if the job never
+/// existed. Aggregates only ever cover the jobs that are left. OTLP drops
+/// their series right away and StatsD only sends the values that are
+/// updated, see [`EVICT_JOB`], but the Prometheus registry only drops
+/// series that went idle: they're left out of the rendered text and
+/// protobuf until [`idle::KeepFresh`] has let them age out, and the jobs are
+/// kept here till then.
+static REMOVED_JOBS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Drops the series of a removed job, set by the exporters that can do it
+/// right away.
+static EVICT_JOB: OnceLock<EvictJob> = OnceLock::new();
+
+type EvictJob = Box<dyn Fn(&str) + Send + Sync>;
+
+fn set_job_removed(name: &str, removed: bool) {
+    let mut jobs = REMOVED_JOBS.lock().unwrap_or_else(|e| e.into_inner());
+    if !removed {
+        jobs.remove(name);
+    } else if let Some(evict) = EVICT_JOB.get() {
+        evict(name);
+    } else {
+        jobs.insert(name.to_owned());
+    }
+}
+
+/// The value of the `label`, the job one, of `key`.
+fn job_of<'a>(key: &'a metrics::Key, label: &str) -> Option<&'a str> {
+    key.labels()
+        .find(|job| job.key() == label)
+        .map(|job| job.value())
+}
+
+/// Renders with `render`, which leaves out the jobs it's given and returns
+/// those it found series of. The series of the other jobs are kept from
+/// going idle, and the removed jobs without any left are forgotten.
+fn without_removed_jobs<T>(
+    series: &idle::Series,
+    label: &str,
+    render: impl FnOnce(&BTreeSet<String>) -> (T, BTreeSet<String>),
+) -> T {
+    let mut removed = REMOVED_JOBS.lock().unwrap_or_else(|e| e.into_inner());
+    series.refresh(|key| job_of(key, label).is_some_and(|job| removed.contains(job)));
+    let (rendered, found) = render(&removed);
+    removed.retain(|job| found.contains(job));
+    rendered
+}
+
+/// Drops the series with a `label`, the job one, in `jobs` from Prometheus
+/// text output, and returns the jobs it dropped series of.
+fn strip_jobs(rendered: &str, label: &str, jobs: &BTreeSet<String>) -> (String, BTreeSet<String>) {
+    let mut found = BTreeSet::new();
+    if jobs.is_empty() {
+        return (rendered.to_owned(), found);
+    }
+    let removed: Vec<(&String, String)> = jobs
+        .iter()
+        .map(|job| {
+            let escaped = job
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            (job, format!("{label}=\"{escaped}\""))
+        })
+        .collect();
+
+    let mut out = String::with_capacity(rendered.len());
+    for line in rendered.lines() {
+        let removed = line
+            .split_once('{')
+            .filter(|_| !line.starts_with('#'))
+            .and_then(|(_, series)| {
+                removed.iter().find(|(_, job)| {
+                    series.starts_with(job.as_str()) || series.contains(&format!(",{job}"))
+                })
+            });
+        match removed {
+            Some((job, _)) => {
+                found.insert((*job).clone());
+            }
+            None => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+    (out, found)
+}
+
+/// Shared by the job's tailer and the aggregator. The tailer only uses the
+/// `classifier`, `stat_lines` and the `split` jobs, and queues the rest of
+/// what it reads. The aggregator writes the fields in `apply_update` and
+/// reads them in `report_jobs`, one after the other on the same task, so
+/// stores and loads are `Relaxed`, `dirty` included.
+#[derive(Default)]
+struct JobStatus {
+    /// Value of the `job` label, derived from the log file name.
+    name: String,
+    cov: AtomicU32,
+    /// Highest `cov` seen, survives restarts that reset the live value.
+    cov_peak: AtomicU32,
+    ft: AtomicU32,
+    corp: AtomicU32,
+    lim: AtomicU32,
+    /// Inputs reaching the `-focus_function`, plus one, 0 without one.
+    focus: AtomicU64,
+    exec_s: AtomicU32,
+    corp_size: AtomicU64,
+    /// `SizeUnit` libFuzzer printed `corp_size` in, plus one, 0 if none.
+    corp_size_unit: AtomicU8,
+    /// Files in the `--corpus-dir` of the job, plus one, 0 without one.
+    corp_files: AtomicU64,
+    /// Their total size.
+    corp_bytes: AtomicU64,
+    rss: AtomicU64,
+    oom: AtomicU32,
+    timeout: AtomicU32,
+    crash: AtomicU32,
+    /// Crashes counted from reports, by `type` label.
+    crash_types: Mutex<BTreeMap<String, u64>>,
+    /// Unix time a crash was last counted, 0 if there was none yet.
+    last_crash: AtomicU64,
+    /// Inputs saved, by `kind` and `hash` label.
+    artifacts: Mutex<BTreeMap<(String, String), u64>>,
+    /// Inputs with new coverage, counted from `NEW` lines.
+    new_inputs: AtomicU64,
+    /// Stat lines seen, for `--sample-rate`.
+    stat_lines: AtomicU64,
+    /// Stat lines by the `StatFormat` they parsed as, sampled ones included.
+    stat_formats: [AtomicU64; StatFormat::ALL.len()],
+    /// `StatFormat` of the latest stat line, plus one, 0 if none.
+    format: AtomicU8,
+    /// Functions reached for the first time, counted from `NEW_FUNC` lines.
+    new_funcs: AtomicU64,
+    /// Worker (re)starts, counted from the `INFO: Seed:` banner. Without one,
+    /// a drop in `cov` counts too, it never goes down within a run.
+    restarts: AtomicU64,
+    /// A banner was seen since the last stat line, so a `cov` drop is expected.
+    restart_logged: AtomicBool,
+    job: AtomicU32,
+    dft_time: AtomicU32,
+    /// Fork-mode run time, `dft_time` only means something next to it.
+    time: AtomicU32,
+    /// Throughput between the latest stat lines, unlike the `exec/s:` field,
+    /// which is averaged over the whole run.
+    exec_s_instant: AtomicU32,
+    /// Executions so far, the `#N` of the latest stat line that had one.
+    execs: AtomicU64,
+    /// Baseline for `exec_s_instant`.
+    total_execs: AtomicU64,
+    total_execs_at_ms: AtomicU64,
+    /// Corpus growth in inputs per minute, near zero with a steady exec/s
+    /// means the fuzzer is stuck.
+    corp_rate: AtomicU32,
+    /// Baseline for `corp_rate`.
+    corp_baseline: AtomicU32,
+    corp_baseline_at_ms: AtomicU64,
+    lines: AtomicU64,
+    /// Values were read from a rotated log before the first line.
+    seeded: AtomicBool,
+    /// Lines that are neither stats, events nor auxiliary lines.
+    parse_errors: AtomicU64,
+    /// Auxiliary lines by `AuxLine` kind.
+    aux_lines: [AtomicU64; AuxLine::ALL.len()],
+    /// exec/s of the stat lines since the last report, with
+    /// `--exec-s-histogram`.
+    exec_s_samples: Mutex<Vec<u32>>,
+    /// The latest stat line as parsed, with `--verbose-metrics`.
+    last_stats: Mutex<Option<Parsed>>,
+    /// Where the tailer is in a multi-line report.
+    classifier: Mutex<LineClassifier>,
+    /// `target` label, from the log's file name or banner.
+    target: OnceLock<String>,
+    /// Unix time the log was discovered.
+    added_at: u64,
+    /// Unix time of the last stat line, 0 if there was none yet.
+    last_update: AtomicU64,
+    /// Unix time `cov` last went past `cov_peak`, 0 if it didn't yet. A log
+    /// that keeps moving with flat coverage means the fuzzer plateaued.
+    cov_increased_at: AtomicU64,
+    /// The run finished with `Done N runs`, so the log stopping is expected.
+    /// Cleared when a new run starts.
+    done: AtomicBool,
+    /// Set whenever a field changes, cleared once the job is reported.
+    dirty: AtomicBool,
+    /// Jobs of the targets split off with `--target-prefix-regex`, by prefix.
+    split: Mutex<HashMap<String, Arc<JobStatus>>>,
+}
+
+impl JobStatus {
+    /// A job tailing `log`, published from its first line on. Without a
+    /// `target` from the manifest, the file name may tell.
+    fn new(name: String, log: &Path, target: Option<&str>) -> JobStatus {
+        JOBS_FOUND.fetch_add(1, Ordering::Relaxed);
+        let target = match target {
+            Some(target) => OnceLock::from(target.to_owned()),
+            None => target_from_path(log)
+                .map(OnceLock::from)
+                .unwrap_or_default(),
+        };
+        JobStatus {
+            name,
+            target,
+            added_at: unix_secs(),
+            dirty: AtomicBool::new(true),
+            ..Default::default()
+        }
+    }
+
+    /// Whether there's anything to publish for the job. One that never
+    /// printed a line would only add series of zeroes, and with logs that
+    /// come and go those pile up.
+    fn has_data(&self) -> bool {
+        self.lines.load(Ordering::Relaxed) != 0 || self.seeded.load(Ordering::Relaxed)
+    }
+
+    fn split_jobs(&self) -> Vec<Arc<JobStatus>> {
+        let split = self.split.lock().unwrap_or_else(|e| e.into_inner());
+        split.values().cloned().collect()
+    }
+
+    /// Adds the counts of `job`, which is being dropped, to the ones of the
+    /// jobs retired before it, which the counter totals go on counting.
+    fn retire(&self, job: &JobStatus) {
+        for (retired, count) in [
+            (&self.oom, &job.oom),
+            (&self.timeout, &job.timeout),
+            (&self.crash, &job.crash),
+        ] {
+            retired.fetch_add(count.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+        let counts = [
+            (&self.new_inputs, &job.new_inputs),
+            (&self.new_funcs, &job.new_funcs),
+            (&self.restarts, &job.restarts),
+            (&self.lines, &job.lines),
+            (&self.parse_errors, &job.parse_errors),
+        ];
+        let formats = self.stat_formats.iter().zip(&job.stat_formats);
+        let aux_lines = self.aux_lines.iter().zip(&job.aux_lines);
+        for (retired, count) in counts.into_iter().chain(formats).chain(aux_lines) {
+            retired.fetch_add(count.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+        let mut crash_types = self.crash_types.lock().unwrap_or_else(|e| e.into_inner());
+        let types = job.crash_types.lock().unwrap_or_else(|e| e.into_inner());
+        for (crash_type, count) in types.iter() {
+            *crash_types.entry(crash_type.clone()).or_default() += count;
+        }
+        let last_crash = job.last_crash.load(Ordering::Relaxed);
+        self.last_crash.fetch_max(last_crash, Ordering::Relaxed);
+    }
+
+    /// `job` and, once it's known, `target`.
+    fn labels(&self) -> Vec<(&'static str, String)> {
+        let mut labels = vec![("job", self.name.clone())];
+        labels.extend(self.target.get().map(|target| ("target", target.clone())));
+        labels
+    }
+
+    fn update(&self, parsed: &Parsed) {
+        let now = unix_secs();
+        self.last_update.store(now, Ordering::Relaxed);
+        LAST_UPDATE.store(now, Ordering::Relaxed);
+        self.update_exec_s_instant(parsed.total_execs);
+        self.update_corp_rate(parsed.corp);
+        if parsed.kind == Some(StatKind::New) {
+            self.new_inputs.fetch_add(1, Ordering::Relaxed);
+        }
+        let restart_logged = self.restart_logged.swap(false, Ordering::Relaxed);
+        if !restart_logged && parsed.cov < self.cov.load(Ordering::Relaxed) {
+            self.restarts.fetch_add(1, Ordering::Relaxed);
+        }
+        if parsed.cov > self.cov_peak.load(Ordering::Relaxed) {
+            self.cov_increased_at.store(now, Ordering::Relaxed);
+        }
+        self.store(parsed);
+    }
+
+    /// A stat line that wasn't applied, still a sign of life.
+    fn touch(&self, kind: Option<StatKind>) {
+        let now = unix_secs();
+        self.last_update.store(now, Ordering::Relaxed);
+        LAST_UPDATE.store(now, Ordering::Relaxed);
+        if kind == Some(StatKind::New) {
+            self.new_inputs.fetch_add(1, Ordering::Relaxed);
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Sets the latest values without treating the line as fresh.
+    fn store(&self, parsed: &Parsed) {
+        if parsed.total_execs != 0 {
+            self.execs.store(parsed.total_execs, Ordering::Relaxed);
+        }
+        self.cov.store(parsed.cov, Ordering::Relaxed);
+        self.cov_peak.fetch_max(parsed.cov, Ordering::Relaxed);
+        self.ft.store(parsed.ft, Ordering::Relaxed);
+        self.corp.store(parsed.corp, Ordering::Relaxed);
+        self.lim.store(parsed.lim, Ordering::Relaxed);
+        let focus = parsed.focus.map_or(0, |focus| u64::from(focus) + 1);
+        self.focus.store(focus, Ordering::Relaxed);
+        self.exec_s.store(parsed.exec_s, Ordering::Relaxed);
+        self.corp_size.store(parsed.corp_size, Ordering::Relaxed);
+        let unit = parsed.corp_size_unit.map_or(0, |unit| unit as u8 + 1);
+        self.corp_size_unit.store(unit, Ordering::Relaxed);
+        self.rss.store(parsed.rss, Ordering::Relaxed);
+        self.job.store(parsed.job, Ordering::Relaxed);
+        self.dft_time.store(parsed.dft_time, Ordering::Relaxed);
+        self.time.store(parsed.time, Ordering::Relaxed);
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// The latest values, for sources that only report some of them per line.
+    fn snapshot(&self) -> Parsed {
+        Parsed {
+            cov: self.cov.load(Ordering::Relaxed),
+            ft: self.ft.load(Ordering::Relaxed),
+            corp: self.corp.load(Ordering::Relaxed),
+            corp_size: self.corp_size.load(Ordering::Relaxed),
+            corp_size_unit: match self.corp_size_unit.load(Ordering::Relaxed) {
+                0 => None,
+                unit => SizeUnit::ALL.get(usize::from(unit) - 1).copied(),
+            },
+            lim: self.lim.load(Ordering::Relaxed),
+            focus: match self.focus.load(Ordering::Relaxed) {
+                0 => None,
+                focus => u32::try_from(focus - 1).ok(),
+            },
+            exec_s: self.exec_s.load(Ordering::Relaxed),
+            total_execs: self.total_execs.load(Ordering::Relaxed),
+            rss: self.rss.load(Ordering::Relaxed),
+            oom: self.oom.load(Ordering::Relaxed),
+            timeout: self.timeout.load(Ordering::Relaxed),
+            crash: self.crash.load(Ordering::Relaxed),
+            job: self.job.load(Ordering::Relaxed),
+            dft_time: self.dft_time.load(Ordering::Relaxed),
+            time: self.time.load(Ordering::Relaxed),
+            ..Default::default()
+        }
+    }
+
+    /// Seconds since the last stat line, or since the log was found.
+    fn age(&self, now: u64) -> u64 {
+        match self.last_update.load(Ordering::Relaxed) {
+            0 => now.saturating_sub(self.added_at),
+            since => now.saturating_sub(since),
+        }
+    }
+
+    fn update_exec_s_instant(&self, total_execs: u64) {
+        if total_execs == 0 {
+            return;
+        }
+        let now = unix_millis();
+        let prev_execs = self.total_execs.load(Ordering::Relaxed);
+        let prev_at = self.total_execs_at_ms.load(Ordering::Relaxed);
+        let elapsed = now.saturating_sub(prev_at);
+
+        if prev_at != 0 && total_execs >= prev_execs {
+            // lines can come in bursts, measure over at least a second
+            if elapsed < 1000 {
+                return;
+            }
+            // a corrupt total can be anything, it reads as the highest rate
+            let rate = (total_execs - prev_execs).saturating_mul(1000) / elapsed;
+            let rate = u32::try_from(rate).unwrap_or(u32::MAX);
+            self.exec_s_instant.store(rate, Ordering::Relaxed);
+        }
+        self.total_execs.store(total_execs, Ordering::Relaxed);
+        self.total_execs_at_ms.store(now, Ordering::Relaxed);
+    }
+
+    fn update_corp_rate(&self, corp: u32) {
+        let now = unix_millis();
+        let prev_corp = self.corp_baseline.load(Ordering::Relaxed);
+        let prev_at = self.corp_baseline_at_ms.load(Ordering::Relaxed);
+        let elapsed = now.saturating_sub(prev_at);
+
+        if prev_at != 0 && corp >= prev_corp {
+            // new inputs are rare, a short window would mostly read as zero
+            if elapsed < 60_000 {
+                return;
+            }
+            let rate = u64::from(corp - prev_corp) * 60_000 / elapsed;
+            self.corp_rate.store(rate as u32, Ordering::Relaxed);
+        }
+        self.corp_baseline.store(corp, Ordering::Relaxed);
+        self.corp_baseline_at_ms.store(now, Ordering::Relaxed);
+    }
+
+    /// Takes the crash count the fuzzer reports itself, a higher one means
+    /// a new crash.
+    fn count_crashes(&self, crash: u32) {
+        if self.crash.fetch_max(crash, Ordering::Relaxed) < crash {
+            self.last_crash.store(unix_secs(), Ordering::Relaxed);
+        }
+    }
+
+    fn record(&self, event: Event) {
+        let counter = match event {
+            Event::Oom => &self.oom,
+            Event::Timeout => &self.timeout,
+            Event::Crash => {
+                self.last_crash.store(unix_secs(), Ordering::Relaxed);
+                &self.crash
+            }
+            Event::Restart => {
+                self.restart_logged.store(true, Ordering::Relaxed);
+                self.restarts.fetch_add(1, Ordering::Relaxed);
+                self.done.store(false, Ordering::Relaxed);
+                self.dirty.store(true, Ordering::Relaxed);
+                return;
+            }
+            Event::Done => {
+                self.done.store(true, Ordering::Relaxed);
+                self.dirty.store(true, Ordering::Relaxed);
+                return;
+            }
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+}
+
+struct Job {
+    status: Arc<JobStatus>,
+    task: JoinHandle<()>,
+    /// What `--manifest` said about the log when the job was created.
+    names: Option<manifest::Entry>,
+}
+
+/// Lines that can be queued for the aggregator before the tailers wait for
+/// it. They fall behind on their log rather than buffering unboundedly, the
+/// lines stay on disk until they're read.
+const UPDATES_CAPACITY: usize = 4096;
+
+/// What a tailer read, applied to `job` by the aggregator in `jobs_parser`,
+/// which is the only writer of the job stats.
+struct JobUpdate {
+    job: Arc<JobStatus>,
+    update: Update,
+}
+
+enum Update {
+    /// Any line, including the ones sent as the updates below.
+    Line,
+    Stats(StatFormat, Parsed),
+    /// honggfuzz stats, merged over the current ones.
+    Honggfuzz(honggfuzz::Line),
+    /// Last stats from a rotated log, not a fresh line.
+    Seed(Parsed),
+    /// A stat line skipped by `--sample-rate`.
+    Sampled(StatFormat, Option<StatKind>),
+    Rss(u64),
+    /// What `--corpus-dir` holds.
+    Corpus {
+        files: u64,
+        bytes: u64,
+    },
+    Target(String),
+    Artifact(Artifact),
+    Event(Event),
+    /// A crash report, with the type of the crash.
+    Crash(String),
+    Aux(AuxLine),
+    ParseError,
+}
+
+/// Which parser a stat line of a job log matched, a directory may hold
+/// fork-mode logs next to the `-jobs=N` ones. Only fork-mode lines carry
+/// `time`, `job` and `dft_time` and the oom/timeout/crash counts, only
+/// job-mode ones `lim`, the corpus size and rss.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatFormat {
+    Fork,
+    Job,
+}
+
+impl StatFormat {
+    const ALL: [StatFormat; 2] = [StatFormat::Fork, StatFormat::Job];
+
+    /// Value of the `format` label.
+    fn name(self) -> &'static str {
+        match self {
+            StatFormat::Fork => "fork",
+            StatFormat::Job => "job",
+        }
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn jobs_parser(args: &Arc<Args>, logs_paths: &[PathBuf]) -> anyhow::Result<()> {
+    let shown = check_logs_paths(logs_paths)?;
+
+    let (updates, mut queued) = tokio::sync::mpsc::channel(UPDATES_CAPACITY);
+    let mut jobs = HashMap::new();
+    let retired = JobStatus::default();
+    rescan_jobs(args, logs_paths, &mut jobs, &retired, &updates)?;
+    if jobs.is_empty() {
+        return Err(no_logs_error(args, &shown));
+    }
+    tracing::info!(paths = shown, logs = jobs.len(), "tailing job logs");
+
+    let mut report = tokio::time::interval(Duration::from_secs(args.interval_secs));
+    let mut rescan = tokio::time::interval(Duration::from_secs(args.rescan_secs));
+    rescan.reset();
+    let mut dumps = std::pin::pin!(dump_requests()?);
+    let mut corpus_scan: Option<JoinHandle<()>> = None;
+    loop {
+        tokio::select! {
+            _ = report.tick() => {
+                let statuses = job_statuses(&jobs);
+                let refs: Vec<_> = statuses.iter().map(|job| &**job).collect();
+                report_jobs(&refs, &retired, args);
+                // a scan of large corpora can take longer than an interval
+                let scanning = corpus_scan.as_ref().is_some_and(|scan| !scan.is_finished());
+                if !args.corpus_dir.is_empty() && !scanning {
+                    let (args, updates) = (args.clone(), updates.clone());
+                    corpus_scan = Some(tokio::task::spawn_blocking(move || {
+                        scan_corpora(&args, statuses, &updates);
+                    }));
+                }
+            }
+            Some(()) = dumps.next() => {
+                let statuses = job_statuses(&jobs);
+                let statuses: Vec<_> = statuses.iter().map(|job| &**job).collect();
+                eprint!("{}", format_jobs(&statuses, args, unix_secs()));
+            }
+            _ = rescan.tick() => {
+                if let Err(e) = rescan_jobs(args, logs_paths, &mut jobs, &retired, &updates) {
+                    tracing::warn!("failed to rescan {shown}: {e:?}");
+                }
+            }
+            Some(update) = queued.recv() => apply_update(args, update),
+        }
+    }
+}
+
+/// The jobs of the logs and the targets split off from them.
+fn job_statuses(jobs: &HashMap<PathBuf, Job>) -> Vec<Arc<JobStatus>> {
+    let split = jobs.values().flat_map(|job| job.status.split_jobs());
+    jobs.values()
+        .map(|job| job.status.clone())
+        .chain(split)
+        .collect()
+}
+
+/// A table of the jobs' latest values, printed on SIGUSR1 to see what the
+/// aggregates are computed from without scraping.
+fn format_jobs(jobs: &[&JobStatus], args: &Args, now: u64) -> String {
+    use std::fmt::Write;
+
+    let mut jobs = jobs.to_vec();
+    jobs.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:<24} {:<16} {:>8} {:>8} {:>8} {:>8} {:>12} {:>6} {:>10} {:>8} {:>10}  state",
+        "job",
+        "target",
+        "cov",
+        "ft",
+        "corp",
+        "exec/s",
+        "rss",
+        "crash",
+        "lines",
+        "errors",
+        "updated"
+    );
+    for job in jobs {
+        let parsed = job.snapshot();
+        let last_update = job.last_update.load(Ordering::Relaxed);
+        let updated = match last_update {
+            0 => "never".to_owned(),
+            at => format!("{}s ago", now.saturating_sub(at)),
+        };
+        let state = if !job.has_data() {
+            "no data"
+        } else if job.done.load(Ordering::Relaxed) {
+            "done"
+        } else if args.stale_after != 0 && job.age(now) > args.stale_after {
+            "stale"
+        } else {
+            "active"
+        };
+        let _ = writeln!(
+            out,
+            "{:<24} {:<16} {:>8} {:>8} {:>8} {:>8} {:>12} {:>6} {:>10} {:>8} {:>10}  {state}",
+            job.name,
+            job.target.get().map_or("-", String::as_str),
+            parsed.cov,
+            parsed.ft,
+            parsed.corp,
+            parsed.exec_s,
+            parsed.rss,
+            parsed.crash,
+            job.lines.load(Ordering::Relaxed),
+            job.parse_errors.load(Ordering::Relaxed),
+            updated,
+        );
+    }
+    out
+}
+
+/// The logs to tail by their job names, keeping the names in `known`. Others
+/// are named by the manifest or after their files.
+fn job_logs(
+    args: &Args,
+    logs_paths: &[PathBuf],
+    known: &HashMap<PathBuf, String>,
+    manifest: &Manifest,
+) -> anyhow::Result<HashMap<PathBuf, String>> {
+    let mut found = Vec::new();
+    for path in logs_paths {
+        found.extend(find_logs(path)?);
+    }
+    found.retain(|(log, _)| args.tails(log));
+    found.sort();
+    found.dedup_by(|(a, _), (b, _)| a == b);
+
+    // Names are only unique within one directory, fall back to the whole
+    // path for a log that would take an existing one.
+    let mut names: HashSet<String> = known.values().cloned().collect();
+    let mut logs = HashMap::new();
+    for (log, name) in found {
+        let name = match known.get(&log) {
+            Some(name) => name.clone(),
+            None => {
+                let named = manifest.job(&log).filter(|named| {
+                    let taken = names.contains(*named);
+                    if taken {
+                        tracing::warn!(log = %log.display(), "job name {named} from the manifest is taken");
+                    }
+                    !taken
+                });
+                match named {
+                    Some(named) => named.to_owned(),
+                    None if !names.contains(&name) => name,
+                    None => job_name(&log),
+                }
+            }
+        };
+        names.insert(name.clone());
+        logs.insert(log, name);
+    }
+    Ok(logs)
+}
+
+/// Checks every path and joins them for messages.
+fn check_logs_paths(logs_paths: &[PathBuf]) -> anyhow::Result<String> {
+    for path in logs_paths {
+        check_logs_path(path)?;
+    }
+    Ok(logs_paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+fn no_logs_error(args: &Args, shown: &str) -> anyhow::Error {
+    if args.include.is_some() || args.exclude.is_some() {
+        anyhow::anyhow!("no log files in {shown} match --include/--exclude")
+    } else {
+        anyhow::anyhow!("no log files found in {shown}")
+    }
+}
+
+/// `--once`: reads the logs to the end instead of tailing them and prints
+/// the metrics they add up to as JSON, the aggregates under `total` and the
+/// rest by job.
+fn summarize_logs(args: &Args) -> anyhow::Result<()> {
+    use std::io::BufRead;
+
+    let shown = check_logs_paths(&args.paths)?;
+    let manifest = args.load_manifest()?;
+    let logs = job_logs(args, &args.paths, &HashMap::new(), &manifest)?;
+    if logs.is_empty() {
+        return Err(no_logs_error(args, &shown));
+    }
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || -> anyhow::Result<()> {
+        let mut jobs = Vec::new();
+        for (log, name) in logs {
+            let job = Arc::new(JobStatus::new(name, &log, manifest.target(&log)));
+            let file = std::fs::File::open(&log)
+                .with_context(|| format!("failed to open {}", log.display()))?;
+            for line in std::io::BufReader::new(file).split(b'\n') {
+                let line = line.with_context(|| format!("failed to read {}", log.display()))?;
+                let line = String::from_utf8_lossy(&line);
+                for line in tail::split_cr(&line) {
+                    let (job, line) = line_job(args, &job, line);
+                    for update in line_updates(args, &job, line) {
+                        let job = job.clone();
+                        apply_update(args, JobUpdate { job, update });
+                    }
+                }
+            }
+            jobs.extend(job.split_jobs());
+            jobs.push(job);
+        }
+        let statuses: Vec<_> = jobs.iter().map(|job| &**job).collect();
+        report_jobs(&statuses, &JobStatus::default(), args);
+        Ok(())
+    })?;
+
+    let mut total = serde_json::Map::new();
+    let mut jobs: BTreeMap<String, serde_json::Map<String, serde_json::Value>> = BTreeMap::new();
+    for (key, _, _, value) in snapshotter.snapshot().into_vec() {
+        let value = match value {
+            DebugValue::Counter(value) => value.into(),
+            DebugValue::Gauge(value) if value.fract() == 0.0 => (value.into_inner() as i64).into(),
+            DebugValue::Gauge(value) => value.into_inner().into(),
+            // the every-line exec/s values, nothing to sum up
+            DebugValue::Histogram(_) => continue,
+        };
+        let key = key.key();
+        let mut job = None;
+        let mut target = None;
+        let mut labels = Vec::new();
+        for label in key.labels() {
+            match label.key() {
+                "job" => job = Some(label.value()),
+                "target" => target = Some(label.value()),
+                _ => labels.push(format!("{}=\"{}\"", label.key(), label.value())),
+            }
+        }
+        let name = if labels.is_empty() {
+            key.name().to_owned()
+        } else {
+            format!("{}{{{}}}", key.name(), labels.join(","))
+        };
+        match job {
+            Some(job) => {
+                let job = jobs.entry(job.to_owned()).or_default();
+                if let Some(target) = target {
+                    job.insert("target".to_owned(), target.into());
+                }
+                job.insert(name, value)
+            }
+            None => total.insert(name, value),
+        };
+    }
+    let summary = serde_json::json!({ "total": total, "jobs": jobs });
+    println!("{summary:#}");
+    Ok(())
+}
+
+/// Starts tailing logs that appeared since the last scan and stops tailing
+/// the ones that are gone, whose counts are added to `retired`.
+fn rescan_jobs(
+    args: &Arc<Args>,
+    logs_paths: &[PathBuf],
+    jobs: &mut HashMap<PathBuf, Job>,
+    retired: &JobStatus,
+    updates: &Sender<JobUpdate>,
+) -> anyhow::Result<()> {
+    // an edited manifest takes effect here, or not at all while it's broken
+    let manifest = args.load_manifest()?;
+    let renamed = |log: &Path, job: &Job| job.names.as_ref() != manifest.entry(log);
+    let names = jobs
+        .iter()
+        .filter(|(log, job)| !renamed(log, job))
+        .map(|(log, job)| (log.clone(), job.status.name.clone()))
+        .collect();
+    let logs = job_logs(args, logs_paths, &names, &manifest)?;
+
+    let retire = |job: &Job| {
+        retired.retire(&job.status);
+        for split in job.status.split_jobs() {
+            retired.retire(&split);
+        }
+    };
+    jobs.retain(|path, job| {
+        let gone = !logs.contains_key(path);
+        if gone || renamed(path, job) {
+            if gone {
+                tracing::info!("{} is gone, stopped tailing it", path.display());
+            } else {
+                // labels can't change, the job starts over under its new names
+                tracing::info!("{} was renamed in the manifest", path.display());
+            }
+            set_job_removed(&job.status.name, true);
+            for split in job.status.split_jobs() {
+                set_job_removed(&split.name, true);
+            }
+            retire(job);
+            return false;
+        }
+        if !job.task.is_finished() {
+            return true;
+        }
+        match (&mut job.task).now_or_never() {
+            // a parser bug, the job keeps its values and tailing goes on
+            Some(Err(e)) if e.is_panic() => {
+                tracing::warn!("tailing {} panicked, restarting it: {e}", path.display());
+                metrics::counter!("task_panics_total").increment(1);
+                job.task = tokio::spawn(seed_and_tail_job(
+                    args.clone(),
+                    path.clone(),
+                    job.status.clone(),
+                    updates.clone(),
+                    false,
+                ));
+                true
+            }
+            // gave up on it, e.g. it was gone before it could be opened, so
+            // it's tried again
+            _ => {
+                retire(job);
+                false
+            }
+        }
+    });
+
+    for (log, name) in logs {
+        if jobs.contains_key(&log) {
+            continue;
+        }
+        set_job_removed(&name, false);
+        let names = manifest.entry(&log).cloned();
+        let target = names.as_ref().and_then(|names| names.target.as_deref());
+        let status = Arc::new(JobStatus::new(name, &log, target));
+        let task = tokio::spawn(seed_and_tail_job(
+            args.clone(),
+            log.clone(),
+            status.clone(),
+            updates.clone(),
+            true,
+        ));
+        jobs.insert(
+            log,
+            Job {
+                status,
+                task,
+                names,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Tails `log` into `job`, seeded from the rotated log with `seed`. A
+/// restarted job has its values already.
+async fn seed_and_tail_job(
+    args: Arc<Args>,
+    log: PathBuf,
+    job: Arc<JobStatus>,
+    updates: Sender<JobUpdate>,
+    seed: bool,
+) {
+    let wait = Duration::from_secs(args.log_wait_secs);
+    let stream = match tail::stream_lines(&log, wait).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            tracing::warn!("failed to tail {}: {e:?}", log.display());
+            return;
+        }
+    };
+    if !seed {
+        tail_job(args, stream, job, updates).await;
+        return;
+    }
+    let (status, seed) = (job.clone(), updates.clone());
+    let seeded = tokio::task::spawn_blocking(move || {
+        if status.target.get().is_none()
+            && let Some(target) = target_from_banner(&log)
+        {
+            let _ = status.target.set(target);
+        }
+        seed_from_rotated(&log, status, &seed)
+    });
+    if let Err(e) = seeded.await {
+        tracing::warn!("failed to seed {}: {e}", job.name);
+    }
+    tail_job(args, stream, job, updates).await;
+}
+
+/// Counts the files in the `--corpus-dir` of every job and queues them for
+/// the aggregator.
+fn scan_corpora(args: &Args, jobs: Vec<Arc<JobStatus>>, updates: &Sender<JobUpdate>) {
+    for job in jobs {
+        let Some(dir) = corpus_dir(args, &job) else {
+            continue;
+        };
+        match count_files(&dir) {
+            Ok((files, bytes)) => {
+                let update = Update::Corpus { files, bytes };
+                let _ = updates.blocking_send(JobUpdate { job, update });
+            }
+            // not created by the fuzzer yet
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("failed to read {}: {e}", dir.display()),
+        }
+    }
+}
+
+/// The `--corpus-dir` given for `job`, or the one named after it or its
+/// target in a directory of corpora.
+fn corpus_dir(args: &Args, job: &JobStatus) -> Option<PathBuf> {
+    let given = args
+        .corpus_dir
+        .iter()
+        .find(|(name, _)| name.as_deref() == Some(job.name.as_str()));
+    if let Some((_, dir)) = given {
+        return Some(dir.clone());
+    }
+    let names: Vec<&str> = std::iter::once(job.name.as_str())
+        .chain(job.target.get().map(String::as_str))
+        .collect();
+    args.corpus_dir
+        .iter()
+        .filter(|(name, _)| name.is_none())
+        .flat_map(|(_, dir)| names.iter().map(|name| dir.join(name)))
+        .find(|dir| dir.is_dir())
+}
+
+/// Regular files in `dir` and their total size. libFuzzer keeps its corpus
+/// flat, so subdirectories aren't counted.
+fn count_files(dir: &Path) -> std::io::Result<(u64, u64)> {
+    let (mut files, mut bytes) = (0, 0);
+    for entry in std::fs::read_dir(dir)? {
+        // inputs come and go while it's read, on a merge or a reduce
+        let Ok(metadata) = entry?.metadata() else {
+            continue;
+        };
+        if metadata.is_file() {
+            files += 1;
+            bytes += metadata.len();
+        }
+    }
+    Ok((files, bytes))
+}
+
+/// Tailing starts at the end of the log, past the banner that names the
+/// target of `fuzz-<N>.log`, so it's looked up at the top.
+fn target_from_banner(log: &Path) -> Option<String> {
+    use std::io::BufRead;
+
+    const BANNER_LINES: usize = 64;
+    if tail::is_fifo(log) {
+        // the lines read here would be gone from the pipe
+        return None;
+    }
+    let file = std::fs::File::open(log).ok()?;
+    std::io::BufReader::new(file)
+        .lines()
+        .take(BANNER_LINES)
+        .map_while(Result::ok)
+        .find_map(|line| target_from_log(&line).map(target_label))
+}
+
+/// Tailing starts at the end of the log, so right after a rotation there are
+/// no values until the next stat line. The newest compressed log has the last
+/// ones.
+fn seed_from_rotated(log: &Path, job: Arc<JobStatus>, updates: &Sender<JobUpdate>) {
+    let Some(rotated) = tail::latest_rotated(log) else {
+        return;
+    };
+    let lines = match tail::read_gz_lines(&rotated) {
+        Ok(lines) => lines,
+        Err(e) => {
+            tracing::warn!("failed to read {}: {e:?}", rotated.display());
+            return;
+        }
+    };
+    if let Some(parsed) = lines
+        .filter_map(|line| {
+            Parsed::from_log(&line)
+                .or_else(|_| Parsed::from_log_job(&line))
+                .ok()
+        })
+        .last()
+    {
+        tracing::info!("seeded {} from {}", job.name, rotated.display());
+        let _ = updates.blocking_send(JobUpdate {
+            job,
+            update: Update::Seed(parsed),
+        });
+    }
+}
+
+/// Parses the lines of a job log and queues what they say for the aggregator.
+async fn tail_job(
+    args: Arc<Args>,
+    stream: impl Stream<Item = String>,
+    job: Arc<JobStatus>,
+    updates: Sender<JobUpdate>,
+) {
+    let mut stream = std::pin::pin!(stream);
+
+    while let Some(line) = stream.next().await {
+        let (job, line) = line_job(&args, &job, &line);
+        let mut line_updates = line_updates(&args, &job, line);
+        sample(&args, &job, &mut line_updates);
+        for update in line_updates {
+            let job = job.clone();
+            // only fails once the aggregator is gone, and this task with it
+            let _ = updates.send(JobUpdate { job, update }).await;
+        }
+    }
+}
+
+/// The job a line of `job`'s log belongs to, and the line without the
+/// `--target-prefix-regex` prefix. A target's job is created on its first
+/// line, named like `combined[target]`.
+fn line_job<'a>(args: &Args, job: &Arc<JobStatus>, line: &'a str) -> (Arc<JobStatus>, &'a str) {
+    let Some(prefix) = &args.target_prefix_regex else {
+        return (job.clone(), line);
+    };
+    let Some(captures) = prefix.captures(line) else {
+        return (job.clone(), line);
+    };
+    let (whole, target) = match (captures.get(0), captures.get(1)) {
+        (Some(whole), Some(target)) if whole.start() == 0 => (whole, target.as_str()),
+        _ => return (job.clone(), line),
+    };
+    let mut split = job.split.lock().unwrap_or_else(|e| e.into_inner());
+    let split_job = split.entry(target.to_owned()).or_insert_with(|| {
+        let name = format!("{}[{target}]", job.name);
+        set_job_removed(&name, false);
+        JOBS_FOUND.fetch_add(1, Ordering::Relaxed);
+        Arc::new(JobStatus {
+            name,
+            target: OnceLock::from(target_label(target)),
+            added_at: unix_secs(),
+            dirty: AtomicBool::new(true),
+            ..Default::default()
+        })
+    });
+    (split_job.clone(), &line[whole.end()..])
+}
+
+/// With `--sample-rate N`, all but every Nth stat line of a job are only
+/// counted. The first and last lines of a run are always applied.
+fn sample(args: &Args, job: &JobStatus, updates: &mut [Update]) {
+    if args.sample_rate == 1 {
+        return;
+    }
+    for update in updates.iter_mut() {
+        let Update::Stats(format, parsed) = update else {
+            continue;
+        };
+        if matches!(parsed.kind, Some(StatKind::Inited | StatKind::Done)) {
+            continue;
+        }
+        let seen = job.stat_lines.fetch_add(1, Ordering::Relaxed);
+        if !seen.is_multiple_of(args.sample_rate) {
+            *update = Update::Sampled(*format, parsed.kind);
+        }
+    }
+}
+
+/// What one line of a job log says, starting with `Update::Line`.
+fn line_updates(args: &Args, job: &JobStatus, line: &str) -> Vec<Update> {
+    let mut updates = vec![Update::Line];
+    let mut classifier = job.classifier.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = if args.honggfuzz {
+        honggfuzz::Line::from_log(line).ok().map(Update::Honggfuzz)
+    } else if let Ok(parsed) = Parsed::from_log(line) {
+        // the stricter parser first, job mode would take `exec/s:` fork lines
+        Some(Update::Stats(StatFormat::Fork, parsed))
+    } else {
+        let parsed = Parsed::from_log_job(line).ok();
+        parsed.map(|parsed| Update::Stats(StatFormat::Job, parsed))
+    };
+    if let Some(stats) = stats {
+        classifier.reset();
+        updates.push(stats);
+        return updates;
+    }
+
+    if let Some(rss) = rss_from_log(line) {
+        updates.push(Update::Rss(rss));
+    }
+    if let Some(target) = target_from_log(line) {
+        updates.push(Update::Target(target_label(target)));
+    }
+    if let Ok(artifact) = Artifact::from_log(line) {
+        updates.push(Update::Artifact(artifact));
+    }
+    // every line goes through the classifier, to keep track of reports
+    let aux = classifier.classify(line);
+    updates.push(if let Ok(event) = Event::from_log(line) {
+        match event {
+            Event::Crash => {
+                let crash_type = crash_type_from_log(line);
+                Update::Crash(crash_type.unwrap_or_else(|| "unknown".to_owned()))
+            }
+            event => Update::Event(event),
+        }
+    } else if let Some(aux) = aux {
+        Update::Aux(aux)
+    } else {
+        tracing::debug!(job = job.name, line, "failed to parse");
+        Update::ParseError
+    });
+    updates
+}
+
+fn apply_update(args: &Args, JobUpdate { job, update }: JobUpdate) {
+    let parsed = match update {
+        Update::Line => {
+            job.lines.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        Update::Stats(format, parsed) => {
+            job.stat_formats[format as usize].fetch_add(1, Ordering::Relaxed);
+            job.format.store(format as u8 + 1, Ordering::Relaxed);
+            if format == StatFormat::Fork {
+                // counted by the fork-mode parent itself
+                job.oom.fetch_max(parsed.oom, Ordering::Relaxed);
+                job.timeout.fetch_max(parsed.timeout, Ordering::Relaxed);
+                job.count_crashes(parsed.crash);
+            }
+            parsed
+        }
+        Update::Honggfuzz(stats) => {
+            let parsed = stats.into_parsed(job.snapshot());
+            // counted by honggfuzz itself, unlike libFuzzer
+            job.count_crashes(parsed.crash);
+            job.timeout.fetch_max(parsed.timeout, Ordering::Relaxed);
+            parsed
+        }
+        Update::Seed(parsed) => {
+            job.seeded.store(true, Ordering::Relaxed);
+            job.store(&parsed);
+            return;
+        }
+        Update::Sampled(format, kind) => {
+            job.stat_formats[format as usize].fetch_add(1, Ordering::Relaxed);
+            job.format.store(format as u8 + 1, Ordering::Relaxed);
+            job.touch(kind);
+            return;
+        }
+        Update::Rss(rss) => {
+            job.rss.store(rss, Ordering::Relaxed);
+            return;
+        }
+        Update::Corpus { files, bytes } => {
+            // counted every interval, mostly the same
+            let files = job.corp_files.swap(files + 1, Ordering::Relaxed) != files + 1;
+            let bytes = job.corp_bytes.swap(bytes, Ordering::Relaxed) != bytes;
+            if files || bytes {
+                job.dirty.store(true, Ordering::Relaxed);
+            }
+            return;
+        }
+        Update::Target(target) => {
+            if job.target.set(target).is_ok() {
+                job.dirty.store(true, Ordering::Relaxed);
+            }
+            return;
+        }
+        Update::Artifact(artifact) => {
+            let mut artifacts = job.artifacts.lock().unwrap_or_else(|e| e.into_inner());
+            *artifacts.entry((artifact.kind, artifact.hash)).or_default() += 1;
+            drop(artifacts);
+            job.dirty.store(true, Ordering::Relaxed);
+            return;
+        }
+        Update::Event(event) => {
+            job.record(event);
+            return;
+        }
+        Update::Crash(crash_type) => {
+            let mut crash_types = job.crash_types.lock().unwrap_or_else(|e| e.into_inner());
+            *crash_types.entry(crash_type).or_default() += 1;
+            drop(crash_types);
+            job.record(Event::Crash);
+            return;
+        }
+        Update::Aux(aux) => {
+            if aux == AuxLine::NewFunc {
+                job.new_funcs.fetch_add(1, Ordering::Relaxed);
+            }
+            job.aux_lines[aux as usize].fetch_add(1, Ordering::Relaxed);
+            job.dirty.store(true, Ordering::Relaxed);
+            return;
+        }
+        Update::ParseError => {
+            job.parse_errors.fetch_add(1, Ordering::Relaxed);
+            job.dirty.store(true, Ordering::Relaxed);
+            return;
+        }
+    };
+
+    if args.exec_s_histogram {
+        let mut samples = job.exec_s_samples.lock().unwrap_or_else(|e| e.into_inner());
+        samples.push(parsed.exec_s);
+    }
+    job.update(&parsed);
+    if args.verbose_metrics {
+        *job.last_stats.lock().unwrap_or_else(|e| e.into_inner()) = Some(parsed);
+    }
+}
+
+fn report_jobs(jobs: &[&JobStatus], retired: &JobStatus, args: &Args) {
+    // Every metric is published per job with a `job` label, plus an
+    // unlabeled aggregate across all jobs. Looking up the labeled series is
+    // what's expensive, so jobs that didn't change keep their last values.
+    // Jobs without data stay dirty until they have some.
+    // Metrics are only published here, updates only touch the `JobStatus`,
+    // so a recorder that blocks for a scrape never holds up the lines.
+    let dirty: Vec<&JobStatus> = jobs
+        .iter()
+        .copied()
+        .filter(|job| job.has_data() && job.dirty.swap(false, Ordering::Relaxed))
+        .collect();
+    // Gauge aggregates only count jobs that updated within `stale_after`.
+    let (now, stale_after) = (unix_secs(), args.stale_after);
+    let live: Vec<&JobStatus> = jobs
+        .iter()
+        .copied()
+        .filter(|job| stale_after == 0 || job.age(now) <= stale_after)
+        .collect();
+    // Counter totals count every job, plus the final counts of the ones
+    // removed since, so they don't go backwards when a log goes away.
+    let with_retired: Vec<&JobStatus> = jobs.iter().copied().chain([retired]).collect();
+    let aggregation = |metric: &str, default| {
+        let configured = args.aggregate.iter().rev().find(|(name, _)| name == metric);
+        configured.map_or(default, |&(_, aggregation)| aggregation)
+    };
+
+    macro_rules! update_metric {
+        (@jobs $field:ident, $metric:expr) => {{
+            for job in dirty.iter() {
+                let value = job.$field.load(Ordering::Relaxed);
+                metrics::gauge!($metric, &job.labels()).set(value as f64);
+            }
+        }};
+        // the aggregate only, `--aggregate` takes precedence over `$default`
+        (@aggregate $jobs:expr, $field:ident, $default:expr, $metric:expr) => {{
+            let values: Vec<f64> = $jobs
+                .map(|job| u64::from(job.$field.load(Ordering::Relaxed)) as f64)
+                .collect();
+            metrics::gauge!($metric).set(aggregation($metric, $default).apply(&values));
+        }};
+        ($field:ident, max, $metric:expr) => {{
+            update_metric!(@jobs $field, $metric);
+            update_metric!(@aggregate live.iter(), $field, Aggregation::Max, $metric);
+        }};
+        ($field:ident, sum, $metric:expr) => {{
+            update_metric!(@jobs $field, $metric);
+            update_metric!($field, total, $metric);
+        }};
+        // the sum only, for a field already published per job under another name
+        ($field:ident, total, $metric:expr) => {{
+            update_metric!(@aggregate live.iter(), $field, Aggregation::Sum, $metric);
+        }};
+        // the min only, like `total`, over the jobs that printed stats so the
+        // zeros of a new one don't win
+        ($field:ident, min, $metric:expr) => {{
+            let updated = live
+                .iter()
+                .filter(|job| job.last_update.load(Ordering::Relaxed) != 0);
+            update_metric!(@aggregate updated, $field, Aggregation::Min, $metric);
+        }};
+        ($field:ident, counter, $metric:expr) => {{
+            for job in dirty.iter() {
+                let value = u64::from(job.$field.load(Ordering::Relaxed));
+                metrics::counter!($metric, &job.labels()).absolute(value);
+            }
+            let total = with_retired
+                .iter()
+                .map(|job| u64::from(job.$field.load(Ordering::Relaxed)))
+                .sum();
+            metrics::counter!($metric).absolute(total);
+        }};
+    }
+
+    update_metric!(cov, max, "cov");
+    update_metric!(cov, min, "cov_min");
+    update_metric!(cov_peak, max, "cov_peak");
+    update_metric!(ft, max, "feat");
+    update_metric!(corp, max, "corp");
+    update_metric!(corp, total, "corp_total");
+    update_metric!(corp_rate, sum, "corp_rate");
+    update_metric!(lim, max, "lim");
+    update_metric!(exec_s, sum, "exec_s");
+    update_metric!(exec_s_instant, sum, "exec_s_instant");
+    update_metric!(corp_size, max, "corp_size");
+    update_metric!(corp_size, total, "corp_size_total");
+    update_metric!(rss, max, "rss");
+    update_metric!(oom, counter, "oom");
+    update_metric!(timeout, counter, "timeout");
+    update_metric!(crash, counter, "crash");
+    update_metric!(job, max, "job");
+    update_metric!(dft_time, max, "dft_time");
+    update_metric!(new_inputs, counter, "new_total");
+    update_metric!(new_funcs, counter, "new_funcs_total");
+    update_metric!(restarts, counter, "restarts_total");
+    update_metric!(lines, counter, "lines_total");
+    update_metric!(parse_errors, counter, "parse_errors_total");
+
+    // per live job, comparable across fleets of different sizes
+    let exec_s: u64 = live
+        .iter()
+        .map(|job| u64::from(job.exec_s.load(Ordering::Relaxed)))
+        .sum();
+    let avg = if live.is_empty() {
+        0.0
+    } else {
+        exec_s as f64 / live.len() as f64
+    };
+    metrics::gauge!("exec_s_avg").set(avg);
+    metrics::gauge!("jobs_active").set(live.len() as f64);
+    metrics::counter!("jobs_total").absolute(JOBS_FOUND.load(Ordering::Relaxed));
+
+    for job in dirty.iter() {
+        let samples =
+            std::mem::take(&mut *job.exec_s_samples.lock().unwrap_or_else(|e| e.into_inner()));
+        if !samples.is_empty() {
+            let histogram = metrics::histogram!("exec_s_hist", &job.labels());
+            for exec_s in samples {
+                histogram.record(f64::from(exec_s));
+            }
+        }
+        if let Some(parsed) = &*job.last_stats.lock().unwrap_or_else(|e| e.into_inner()) {
+            publish_line_fields(parsed, &job.labels());
+        }
+    }
+
+    // crashes by the type of their report too, next to the untyped series
+    let mut crash_types = BTreeMap::new();
+    for job in with_retired.iter() {
+        let types = job.crash_types.lock().unwrap_or_else(|e| e.into_inner());
+        for (crash_type, count) in types.iter() {
+            *crash_types.entry(crash_type.clone()).or_default() += count;
+        }
+    }
+    for job in dirty.iter() {
+        let types = job.crash_types.lock().unwrap_or_else(|e| e.into_inner());
+        for (crash_type, &count) in types.iter() {
+            let mut labels = job.labels();
+            labels.push(("type", crash_type.clone()));
+            metrics::counter!("crash", &labels).absolute(count);
+        }
+    }
+    for (crash_type, count) in crash_types {
+        metrics::counter!("crash", "type" => crash_type).absolute(count);
+    }
+
+    for job in dirty.iter() {
+        let artifacts = job.artifacts.lock().unwrap_or_else(|e| e.into_inner());
+        for ((kind, hash), &count) in artifacts.iter() {
+            let mut labels = job.labels();
+            labels.extend([("kind", kind.clone()), ("hash", hash.clone())]);
+            metrics::counter!("artifacts_total", &labels).absolute(count);
+        }
+    }
+
+    // oom, timeout and crash together, for a single alert
+    let incidents = |job: &JobStatus| {
+        [&job.oom, &job.timeout, &job.crash]
+            .iter()
+            .map(|count| u64::from(count.load(Ordering::Relaxed)))
+            .sum::<u64>()
+    };
+    for job in dirty.iter() {
+        metrics::counter!("incidents_total", &job.labels()).absolute(incidents(job));
+    }
+    let total = with_retired.iter().map(|job| incidents(job)).sum();
+    metrics::counter!("incidents_total").absolute(total);
+
+    // when the exporter counted the crash, the aggregate is the latest one
+    let last_crash = |job: &JobStatus| job.last_crash.load(Ordering::Relaxed);
+    for job in dirty.iter().filter(|job| last_crash(job) != 0) {
+        metrics::gauge!("last_crash_timestamp", &job.labels()).set(last_crash(job) as f64);
+    }
+    let latest = with_retired
+        .iter()
+        .map(|job| last_crash(job))
+        .max()
+        .unwrap_or(0);
+    if latest != 0 {
+        metrics::gauge!("last_crash_timestamp").set(latest as f64);
+    }
+
+    // Ratios of two fields: jobs where it's undefined, with nothing to divide
+    // by yet, have no series and are left out of the aggregate, which by
+    // default averages the others'.
+    let ratio = |metric: &'static str, ratio: &dyn Fn(&JobStatus) -> Option<f64>| {
+        for job in dirty.iter() {
+            if let Some(value) = ratio(job) {
+                metrics::gauge!(metric, &job.labels()).set(value);
+            }
+        }
+        let values: Vec<f64> = live.iter().filter_map(|job| ratio(job)).collect();
+        if !values.is_empty() {
+            metrics::gauge!(metric).set(aggregation(metric, Aggregation::Avg).apply(&values));
+        }
+    };
+
+    // features found per covered edge
+    ratio("ft_per_cov", &|job| {
+        ft_per_cov(
+            job.ft.load(Ordering::Relaxed),
+            job.cov.load(Ordering::Relaxed),
+        )
+    });
+
+    // grows when the fuzzer doesn't minimize its inputs, only job-mode lines
+    // have both
+    ratio("corp_avg_input_bytes", &|job| {
+        let corp = job.corp.load(Ordering::Relaxed);
+        let corp_size = job.corp_size.load(Ordering::Relaxed);
+        (corp != 0).then(|| corp_size as f64 / f64::from(corp))
+    });
+
+    // counted on disk with --corpus-dir, jobs without one have no series. In
+    // -jobs=N mode the jobs share their corpus, so the aggregate is the max.
+    let corpus = |job: &JobStatus| {
+        let files = job.corp_files.load(Ordering::Relaxed).checked_sub(1)?;
+        Some((files, job.corp_bytes.load(Ordering::Relaxed)))
+    };
+    for job in dirty.iter() {
+        if let Some((files, bytes)) = corpus(job) {
+            metrics::gauge!("corp_files", &job.labels()).set(files as f64);
+            metrics::gauge!("corp_bytes", &job.labels()).set(bytes as f64);
+        }
+    }
+    let counted: Vec<(u64, u64)> = live.iter().filter_map(|job| corpus(job)).collect();
+    if !counted.is_empty() {
+        let files: Vec<f64> = counted.iter().map(|&(files, _)| files as f64).collect();
+        let bytes: Vec<f64> = counted.iter().map(|&(_, bytes)| bytes as f64).collect();
+        let files = aggregation("corp_files", Aggregation::Max).apply(&files);
+        metrics::gauge!("corp_files").set(files);
+        let bytes = aggregation("corp_bytes", Aggregation::Max).apply(&bytes);
+        metrics::gauge!("corp_bytes").set(bytes);
+    }
+
+    // whether -focus_function steers the fuzzer, jobs without one have no
+    // series
+    ratio("focus_hit_fraction", &|job| {
+        let focus = job.focus.load(Ordering::Relaxed).checked_sub(1)?;
+        focus_hit_fraction(focus, job.corp.load(Ordering::Relaxed))
+    });
+
+    // coverage for the executions it took
+    ratio("cov_per_mexec", &|job| {
+        cov_per_mexec(
+            job.cov.load(Ordering::Relaxed),
+            job.execs.load(Ordering::Relaxed),
+        )
+    });
+
+    // the run time spent on data-flow traces
+    ratio("dft_fraction", &|job| {
+        dft_fraction(
+            job.dft_time.load(Ordering::Relaxed).into(),
+            job.time.load(Ordering::Relaxed).into(),
+        )
+    });
+
+    // one series per unit, 1 for the one the corpus size is printed in
+    for job in dirty.iter() {
+        let Some(current) = job.snapshot().corp_size_unit else {
+            continue;
+        };
+        for unit in SizeUnit::ALL {
+            let mut labels = job.labels();
+            labels.push(("unit", unit.name().to_owned()));
+            metrics::gauge!("corp_size_unit_info", &labels).set(if unit == current {
+                1.0
+            } else {
+                0.0
+            });
+        }
+    }
+
+    // one series per format, 1 for the one of the latest stat line, so a
+    // shift in libFuzzer's output shows up
+    for job in dirty.iter() {
+        let current = match job.format.load(Ordering::Relaxed) {
+            0 => None,
+            format => StatFormat::ALL.get(usize::from(format) - 1).copied(),
+        };
+        for format in [None, Some(StatFormat::Fork), Some(StatFormat::Job)] {
+            let mut labels = job.labels();
+            labels.push((
+                "format",
+                format.map_or("unknown", StatFormat::name).to_owned(),
+            ));
+            metrics::gauge!("line_format_info", &labels).set(if format == current {
+                1.0
+            } else {
+                0.0
+            });
+        }
+    }
+
+    for format in StatFormat::ALL {
+        let mut total = 0;
+        for job in with_retired.iter() {
+            total += job.stat_formats[format as usize].load(Ordering::Relaxed);
+        }
+        for job in dirty.iter() {
+            let value = job.stat_formats[format as usize].load(Ordering::Relaxed);
+            let mut labels = job.labels();
+            labels.push(("format", format.name().to_owned()));
+            metrics::counter!("stat_lines_total", &labels).absolute(value);
+        }
+        metrics::counter!("stat_lines_total", "format" => format.name()).absolute(total);
+    }
+
+    for aux in AuxLine::ALL {
+        let mut total = 0;
+        for job in with_retired.iter() {
+            total += job.aux_lines[aux as usize].load(Ordering::Relaxed);
+        }
+        for job in dirty.iter() {
+            let value = job.aux_lines[aux as usize].load(Ordering::Relaxed);
+            let mut labels = job.labels();
+            labels.push(("kind", aux.name().to_owned()));
+            metrics::counter!("aux_lines_total", &labels).absolute(value);
+        }
+        metrics::counter!("aux_lines_total", "kind" => aux.name()).absolute(total);
+    }
+
+    let mut done = 0;
+    for job in jobs.iter() {
+        done += u64::from(job.done.load(Ordering::Relaxed));
+    }
+    for job in dirty.iter() {
+        let value = job.done.load(Ordering::Relaxed);
+        metrics::gauge!("job_done", &job.labels()).set(u8::from(value) as f64);
+    }
+    metrics::gauge!("job_done").set(done as f64);
+
+    // Jobs that haven't printed anything yet are stale since they were found,
+    // which only shows in the max as they have no series of their own.
+    // Finished ones are left out of the max, their log is expected to stop.
+    type Since = fn(&JobStatus) -> &AtomicU64;
+    let ages: [(&str, Since); 2] = [
+        ("seconds_since_update", |job| &job.last_update),
+        ("seconds_since_cov_increase", |job| &job.cov_increased_at),
+    ];
+    for (metric, since) in ages {
+        let mut max_age = 0;
+        for job in jobs.iter() {
+            let since = match since(job).load(Ordering::Relaxed) {
+                0 => job.added_at,
+                since => since,
+            };
+            let age = now.saturating_sub(since);
+            if !job.done.load(Ordering::Relaxed) {
+                max_age = max_age.max(age);
+            }
+            if job.has_data() {
+                metrics::gauge!(metric, &job.labels()).set(age as f64);
+            }
+        }
+        metrics::gauge!(metric).set(max_age as f64);
+    }
+}
+
+fn ft_per_cov(ft: u32, cov: u32) -> Option<f64> {
+    (cov != 0).then(|| f64::from(ft) / f64::from(cov))
+}
+
+fn focus_hit_fraction(focus: u64, corp: u32) -> Option<f64> {
+    (corp != 0).then(|| (focus as f64 / f64::from(corp)).min(1.0))
+}
+
+fn cov_per_mexec(cov: u32, execs: u64) -> Option<f64> {
+    (execs != 0).then(|| f64::from(cov) / (execs as f64 / 1e6))
+}
+
+fn dft_fraction(dft_time: u64, time: u64) -> Option<f64> {
+    (time != 0).then(|| dft_time as f64 / time as f64)
+}
+
+fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn unix_secs() -> u64 {
+    unix_millis() / 1000
+}
+
+/// Finds the logs to tail, either a single log file, the `*.log` files in a
+/// directory or the files matching a glob pattern like `jobs/**/*.log`,
+/// along with their job names.
+fn find_logs(path: &Path) -> anyhow::Result<Vec<(PathBuf, String)>> {
+    if path.is_file() || tail::is_fifo(path) {
+        let name = job_name(Path::new(path.file_name().unwrap_or_default()));
+        return Ok(vec![(path.to_path_buf(), name)]);
+    }
+
+    let arg = path
+        .to_str()
+        .with_context(|| format!("{} is not valid UTF-8", path.display()))?;
+    let (base, pattern) = if is_glob(arg) {
+        (glob_base(path), arg.to_owned())
+    } else {
+        let dir = glob::Pattern::escape(arg);
+        (
+            path.to_path_buf(),
+            format!("{}/*.log", dir.trim_end_matches('/')),
+        )
+    };
+
+    let mut logs = Vec::new();
+    let paths = glob::glob(&pattern).with_context(|| format!("invalid pattern {pattern}"))?;
+    for log in paths.filter_map(|entry| entry.ok()) {
+        if !log.is_file() && !tail::is_fifo(&log) {
+            continue;
+        }
+        let name = job_name(log.strip_prefix(&base).unwrap_or(&log));
+        logs.push((log, name));
+    }
+    Ok(logs)
+}
+
+/// Catches a mistyped path before it turns into a vague "no log files found",
+/// for a glob pattern its leading directory is checked.
+fn check_logs_path(path: &Path) -> anyhow::Result<()> {
+    const HINT: &str = "omit the path to read the logs from journalctl instead";
+
+    let glob = is_glob(&path.to_string_lossy());
+    let dir = if glob {
+        glob_base(path)
+    } else {
+        path.to_path_buf()
+    };
+    if dir.as_os_str().is_empty() {
+        // relative pattern like `*.log`
+        return Ok(());
+    }
+    let meta = match std::fs::metadata(&dir) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            anyhow::bail!("{} does not exist, {HINT}", dir.display())
+        }
+        Err(e) => return Err(e).with_context(|| format!("failed to access {}", dir.display())),
+    };
+    anyhow::ensure!(
+        meta.is_dir() || ((meta.is_file() || tail::is_fifo(&dir)) && !glob),
+        "{} is not a directory, {HINT}",
+        dir.display()
+    );
+    Ok(())
+}
+
+fn is_glob(s: &str) -> bool {
+    s.contains(['*', '?', '['])
+}
+
+/// Leading part of a glob pattern without wildcards, `jobs` for `jobs/**/*.log`.
+fn glob_base(pattern: &Path) -> PathBuf {
+    pattern
+        .components()
+        .take_while(|c| !is_glob(&c.as_os_str().to_string_lossy()))
+        .collect()
+}
+
+/// The `target` of a log from its file name, except for libFuzzer's own
+/// `fuzz-<N>.log` job logs, which only the banner names.
+fn target_from_path(log: &Path) -> Option<String> {
+    let stem = log.file_stem()?.to_string_lossy();
+    let job_log = stem
+        .strip_prefix("fuzz-")
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+    (!job_log).then(|| target_label(&stem))
+}
+
+/// Replaces anything but `[a-zA-Z0-9_]` with `_`.
+fn target_label(target: &str) -> String {
+    target
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    metrics_exporter_prometheus::PrometheusBuilder::new().install()?;
-    println!("Starting server...");
+/// Path relative to the scanned directory without the extension,
+/// `fuzz-0.log` -> `fuzz-0`, `target/fuzz.log` -> `target/fuzz`
+fn job_name(path: &Path) -> String {
+    path.with_extension("").to_string_lossy().into_owned()
+}
 
-    let Some(dir_path) = std::env::args().nth(1) else {
-        return journalctl_parser().await;
-    };
-    jobs_parser(Path::new(&dir_path)).await?;
+async fn stdin_parser(args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("starting stdin parser");
+
+    let input = decompressed(tokio::io::BufReader::new(tokio::io::stdin()))
+        .await
+        .context("failed to read stdin")?;
+    fork_mode_parser(args, input).await
+}
+
+/// Decompresses zstd-compressed input, like a pipe from `zstd -c`, told
+/// apart from text by the magic bytes it starts with.
+async fn decompressed(
+    mut input: impl AsyncBufRead + Unpin + Send + 'static,
+) -> std::io::Result<Pin<Box<dyn AsyncBufRead + Send>>> {
+    const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+    // a pipe may hand out fewer bytes than the magic at a time
+    let mut start = Vec::with_capacity(ZSTD_MAGIC.len());
+    while start.len() < ZSTD_MAGIC.len() {
+        let data = input.fill_buf().await?;
+        if data.is_empty() {
+            break;
+        }
+        let len = data.len().min(ZSTD_MAGIC.len() - start.len());
+        start.extend_from_slice(&data[..len]);
+        input.consume(len);
+    }
+    let zstd = start == ZSTD_MAGIC;
+    let input = std::io::Cursor::new(start).chain(input);
+    if !zstd {
+        return Ok(Box::pin(input));
+    }
+    tracing::info!("decompressing zstd input");
+    let mut decoder = async_compression::tokio::bufread::ZstdDecoder::new(input);
+    // `zstd` writes a frame per file, concatenated ones are one log
+    decoder.multiple_members(true);
+    Ok(Box::pin(tokio::io::BufReader::new(decoder)))
+}
+
+async fn fork_mode_parser(
+    args: &Args,
+    input: impl AsyncBufRead + Unpin,
+) -> Result<(), anyhow::Error> {
+    let mut stream = std::pin::pin!(fuzz_exporter::parse_stream(input));
+    let mut classifier = LineClassifier::default();
+    while let Some(parsed) = stream.next().await {
+        metrics::counter!("lines_total").increment(1);
+        match parsed {
+            Ok(parsed) => {
+                classifier.reset();
+                fork_mode_stats(args, &parsed, &[], None);
+            }
+            Err(StreamError::Unparsed(line)) => fork_mode_other(&line, &mut classifier, &[]),
+            Err(e) => return Err(e.into()),
+        }
+    }
 
     Ok(())
 }
-#[derive(Default)]
-struct JobStatus {
-    cov: AtomicU32,
-    ft: AtomicU32,
-    corp: AtomicU32,
-    exec_s: AtomicU32,
-    corp_size: AtomicU64,
+
+/// Publishes one fork-mode line. `labels` tell the sources apart, like the
+/// journal unit, and `logged_at_us` is when the source received the line.
+/// `classifier` follows the source's reports.
+fn fork_mode_line(
+    args: &Args,
+    line: &str,
+    classifier: &mut LineClassifier,
+    labels: &[(&'static str, String)],
+    logged_at_us: Option<u64>,
+) {
+    metrics::counter!("lines_total", labels).increment(1);
+    match Parsed::from_log(line) {
+        Ok(parsed) => {
+            classifier.reset();
+            fork_mode_stats(args, &parsed, labels, logged_at_us);
+        }
+        Err(_) => fork_mode_other(line, classifier, labels),
+    }
 }
 
-impl JobStatus {
-    fn update(&self, parsed: &Parsed) {
-        self.cov.store(parsed.cov, Ordering::Relaxed);
-        self.ft.store(parsed.ft, Ordering::Relaxed);
-        self.corp.store(parsed.corp, Ordering::Relaxed);
-        self.exec_s.store(parsed.exec_s, Ordering::Relaxed);
-        self.corp_size.store(parsed.corp_size, Ordering::Relaxed);
+/// Publishes what a line other than a stat line says.
+fn fork_mode_other(line: &str, classifier: &mut LineClassifier, labels: &[(&'static str, String)]) {
+    if let Some(rss) = rss_from_log(line) {
+        metrics::gauge!("rss", labels).set(rss as f64);
+    }
+    if let Ok(artifact) = Artifact::from_log(line) {
+        let mut labels = labels.to_vec();
+        labels.extend([("kind", artifact.kind), ("hash", artifact.hash)]);
+        metrics::counter!("artifacts_total", &labels).increment(1);
+    }
+    match classifier.classify(line) {
+        Some(aux) => {
+            if aux == AuxLine::NewFunc {
+                metrics::counter!("new_funcs_total", labels).increment(1);
+            }
+            let mut labels = labels.to_vec();
+            labels.push(("kind", aux.name().to_string()));
+            metrics::counter!("aux_lines_total", &labels).increment(1);
+        }
+        None => {
+            tracing::debug!(line, "failed to parse");
+            metrics::counter!("parse_errors_total", labels).increment(1);
+        }
     }
 }
 
-async fn jobs_parser(dir_path: &Path) -> anyhow::Result<()> {
-    let logs = std::fs::read_dir(dir_path)?;
-    let logs: Vec<_> = logs
-        .into_iter()
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "log"))
-        .map(|entry| entry.path())
-        .collect();
+/// Publishes the stats of a fork-mode line.
+fn fork_mode_stats(
+    args: &Args,
+    parsed: &Parsed,
+    labels: &[(&'static str, String)],
+    logged_at_us: Option<u64>,
+) {
+    LAST_UPDATE.store(unix_secs(), Ordering::Relaxed);
+    if args.exec_s_histogram {
+        metrics::histogram!("exec_s_hist", labels).record(parsed.exec_s as f64);
+    }
+    if args.verbose_metrics {
+        publish_line_fields(parsed, labels);
+    }
+    let now = chrono::Local::now();
+    let lag = match logged_at_us {
+        Some(at) => Some((now.timestamp_micros() - at as i64) as f64 / 1e6),
+        None => parsed
+            .timestamp
+            .and_then(|ts| ts.age(now))
+            .map(|lag| lag as f64),
+    };
+    if let Some(lag) = lag {
+        metrics::gauge!("log_lag_seconds", labels).set(lag.max(0.0));
+    }
+    metrics::gauge!("cov", labels).set(parsed.cov as f64);
+    metrics::gauge!("feat", labels).set(parsed.ft as f64);
+    if let Some(ratio) = ft_per_cov(parsed.ft, parsed.cov) {
+        metrics::gauge!("ft_per_cov", labels).set(ratio);
+    }
+    metrics::gauge!("corp", labels).set(parsed.corp as f64);
+    metrics::gauge!("exec_s", labels).set(parsed.exec_s as f64);
+    metrics::counter!("oom", labels).absolute(parsed.oom.into());
+    metrics::counter!("timeout", labels).absolute(parsed.timeout.into());
+    metrics::counter!("crash", labels).absolute(parsed.crash.into());
+    let incidents = u64::from(parsed.oom) + u64::from(parsed.timeout) + u64::from(parsed.crash);
+    metrics::counter!("incidents_total", labels).absolute(incidents);
+    metrics::gauge!("time", labels).set(parsed.time as f64);
+    metrics::counter!("time_seconds_total", labels).absolute(parsed.time.into());
+    metrics::gauge!("job", labels).set(parsed.job as f64);
+    metrics::gauge!("dft_time", labels).set(parsed.dft_time as f64);
+    if let Some(fraction) = dft_fraction(parsed.dft_time.into(), parsed.time.into()) {
+        metrics::gauge!("dft_fraction", labels).set(fraction);
+    }
+    if let Some(value) = cov_per_mexec(parsed.cov, parsed.total_execs) {
+        metrics::counter!("execs_total", labels).absolute(parsed.total_execs);
+        metrics::gauge!("cov_per_mexec", labels).set(value);
+    }
+}
+
+/// Every numeric field of a stat line, for `--verbose-metrics`.
+fn publish_line_fields(parsed: &Parsed, labels: &[(&'static str, String)]) {
+    let fields = [
+        ("cov", parsed.cov.into()),
+        ("ft", parsed.ft.into()),
+        ("corp", parsed.corp.into()),
+        ("corp_size", parsed.corp_size),
+        ("lim", parsed.lim.into()),
+        ("exec_s", parsed.exec_s.into()),
+        ("total_execs", parsed.total_execs),
+        ("rss", parsed.rss),
+        ("oom", parsed.oom.into()),
+        ("timeout", parsed.timeout.into()),
+        ("crash", parsed.crash.into()),
+        ("time", parsed.time.into()),
+        ("job", parsed.job.into()),
+        ("dft_time", parsed.dft_time.into()),
+    ];
+    let focus = parsed.focus.map(|focus| ("focus", focus.into()));
+    for (field, value) in fields.into_iter().chain(focus) {
+        let mut labels = labels.to_vec();
+        labels.push(("field", field.to_owned()));
+        metrics::gauge!("line_field", &labels).set(value as f64);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        Aggregation, Args, JobStatus, JobUpdate, REMOVED_JOBS, StatFormat, Update, apply_update,
+        check_logs_path, decompressed, describe_metrics, find_logs, fork_mode_line, format_jobs,
+        global_labels, idle, job_statuses, jobs_parser, line_job, line_updates, parse_args,
+        report_jobs, rescan_jobs, sample, scan_corpora, strip_jobs, target_from_path, unix_millis,
+        unix_secs, without_removed_jobs,
+    };
+    use fuzz_exporter::{AuxLine, Parsed};
+    use metrics_exporter_prometheus::PrometheusHandle;
+    use metrics_util::MetricKindMask;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use std::collections::{BTreeSet, HashMap};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    #[test]
+    fn test_strip_jobs() {
+        let rendered = "\
+# TYPE fuzz_cov gauge
+fuzz_cov{job=\"fuzz-0\"} 10
+fuzz_cov{job=\"fuzz-1\"} 20
+fuzz_cov{job=\"fuzz-10\"} 30
+fuzz_cov 30
+fuzz_aux_lines_total{job=\"fuzz-1\",kind=\"info\"} 2
+fuzz_aux_lines_total{kind=\"info\"} 2
+";
+        let removed = BTreeSet::from(["fuzz-1".to_owned(), "fuzz-2".to_owned()]);
+        let fuzz_1 = BTreeSet::from(["fuzz-1".to_owned()]);
+        assert_eq!(
+            strip_jobs(rendered, "job", &removed),
+            (
+                "\
+# TYPE fuzz_cov gauge
+fuzz_cov{job=\"fuzz-0\"} 10
+fuzz_cov{job=\"fuzz-10\"} 30
+fuzz_cov 30
+fuzz_aux_lines_total{kind=\"info\"} 2
+"
+                .to_owned(),
+                fuzz_1.clone()
+            )
+        );
+        let none = BTreeSet::new();
+        assert_eq!(
+            strip_jobs(rendered, "job", &none),
+            (rendered.to_owned(), none.clone())
+        );
+        // with --pushgateway
+        let pushed = "fuzz_cov{exported_job=\"fuzz-1\"} 20\nfuzz_cov 20\n";
+        assert_eq!(
+            strip_jobs(pushed, "job", &removed),
+            (pushed.to_owned(), none)
+        );
+        assert_eq!(
+            strip_jobs(pushed, "exported_job", &removed),
+            ("fuzz_cov 20\n".to_owned(), fuzz_1)
+        );
+    }
+
+    #[test]
+    fn test_find_logs() {
+        let dir = std::env::temp_dir().join(format!("fuzz-exporter-find-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("target_a")).unwrap();
+        std::fs::write(dir.join("fuzz-0.log"), "").unwrap();
+        std::fs::write(dir.join("build.txt"), "").unwrap();
+        std::fs::write(dir.join("target_a/fuzz.log"), "").unwrap();
+
+        let names = |logs: Vec<(std::path::PathBuf, String)>| {
+            let mut names: Vec<_> = logs.into_iter().map(|(_, name)| name).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(find_logs(&dir).unwrap()), ["fuzz-0"]);
+        assert_eq!(
+            names(find_logs(&dir.join("**/*.log")).unwrap()),
+            ["fuzz-0", "target_a/fuzz"]
+        );
+        assert!(find_logs(&dir.join("**/*.gz")).unwrap().is_empty());
+        assert_eq!(
+            names(find_logs(&dir.join("target_a/fuzz.log")).unwrap()),
+            ["fuzz"]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_include_exclude() {
+        let args: Args = clap::Parser::try_parse_from([
+            "fuzz-exporter",
+            "--include",
+            r"\.log$",
+            "--exclude",
+            "^cargo-",
+            "logs",
+        ])
+        .unwrap();
+        assert!(args.tails(Path::new("logs/fuzz-0.log")));
+        assert!(!args.tails(Path::new("logs/cargo-build.log")));
+        assert!(!args.tails(Path::new("logs/fuzz-0.txt")));
+        // only the file name is matched
+        assert!(args.tails(Path::new("cargo-logs/fuzz-0.log")));
+    }
+
+    #[test]
+    fn test_job_log_lines() {
+        let args: Args = clap::Parser::try_parse_from(["fuzz-exporter", "logs"]).unwrap();
+        let job = Arc::new(JobStatus::default());
+        let log = "\
+#1024\tNEW    cov: 10 ft: 11 corp: 3/512Kb exec/s: 0 rss: 30Mb
+NEW_FUNC[1/2]: 0x55aa in parse_header src/parse.c:42
+==12345== libFuzzer: out-of-memory (malloc(2147483648))
+SUMMARY: libFuzzer: out-of-memory
+==12345== ERROR: libFuzzer: timeout after 25 seconds
+SUMMARY: libFuzzer: timeout
+==12345==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x55aa
+READ of size 1 at 0x602000000011 thread T0
+==12345==ABORTING
+garbage";
+        for line in log.lines() {
+            for update in line_updates(&args, &job, line) {
+                let job = job.clone();
+                apply_update(&args, JobUpdate { job, update });
+            }
+        }
+        assert_eq!(job.cov.load(Ordering::Relaxed), 10);
+        assert_eq!(job.oom.load(Ordering::Relaxed), 1);
+        assert_eq!(job.timeout.load(Ordering::Relaxed), 1);
+        assert_eq!(job.new_funcs.load(Ordering::Relaxed), 1);
+        assert_eq!(job.crash.load(Ordering::Relaxed), 1);
+        let crash_types = job.crash_types.lock().unwrap();
+        assert_eq!(
+            *crash_types,
+            [("heap-buffer-overflow".to_owned(), 1)].into()
+        );
+        assert_eq!(job.lines.load(Ordering::Relaxed), 10);
+        assert_eq!(job.parse_errors.load(Ordering::Relaxed), 1);
+        let report = job.aux_lines[AuxLine::Report as usize].load(Ordering::Relaxed);
+        assert_eq!(report, 4);
+    }
+
+    #[test]
+    fn test_mixed_log_formats() {
+        let args: Args = clap::Parser::try_parse_from(["fuzz-exporter", "logs"]).unwrap();
+        let job = Arc::new(JobStatus::default());
+        let log = "\
+#1024\tNEW    cov: 10 ft: 11 corp: 3/512Kb exec/s: 0 rss: 30Mb
+#2903021619: cov: 2163 ft: 20854 corp: 5491 exec/s: 1410 oom/timeout/crash: 1/2/3 time: 3600s job: 12 dft_time: 0
+#4096\tpulse  cov: 2170 ft: 20900 corp: 5500/2Mb lim: 4096 exec/s: 1500 rss: 412Mb";
+        let mut lines = log.lines();
+        let apply = |lines: &mut std::str::Lines| {
+            for update in line_updates(&args, &job, lines.next().unwrap()) {
+                let job = job.clone();
+                apply_update(&args, JobUpdate { job, update });
+            }
+        };
+
+        apply(&mut lines);
+        assert_eq!(job.rss.load(Ordering::Relaxed), 30 * 1024 * 1024);
+        apply(&mut lines);
+        // `exec/s:` would do for the job-mode parser too
+        assert_eq!(job.cov.load(Ordering::Relaxed), 2163);
+        assert_eq!(job.job.load(Ordering::Relaxed), 12);
+        assert_eq!(job.oom.load(Ordering::Relaxed), 1);
+        assert_eq!(job.timeout.load(Ordering::Relaxed), 2);
+        assert_eq!(job.crash.load(Ordering::Relaxed), 3);
+        apply(&mut lines);
+        assert_eq!(job.cov.load(Ordering::Relaxed), 2170);
+        assert_eq!(job.lim.load(Ordering::Relaxed), 4096);
+        assert_eq!(job.crash.load(Ordering::Relaxed), 3);
+
+        let formats = job
+            .stat_formats
+            .each_ref()
+            .map(|n| n.load(Ordering::Relaxed));
+        assert_eq!(formats, [1, 2]);
+        assert_eq!(
+            job.format.load(Ordering::Relaxed),
+            StatFormat::Job as u8 + 1
+        );
+        assert_eq!(job.parse_errors.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_decompresses_zstd_input() {
+        use tokio::io::AsyncReadExt;
+
+        let read = async |input: Vec<u8>| {
+            let mut text = String::new();
+            let mut input = decompressed(std::io::Cursor::new(input)).await.unwrap();
+            input.read_to_string(&mut text).await.unwrap();
+            text
+        };
+        let compress = async |text: &'static str| {
+            let mut compressed = Vec::new();
+            let mut encoder = async_compression::tokio::bufread::ZstdEncoder::new(text.as_bytes());
+            encoder.read_to_end(&mut compressed).await.unwrap();
+            compressed
+        };
+
+        let mut input = compress("#1\tINITED cov: 2 ft: 2\n").await;
+        input.extend(compress("#2\tNEW cov: 3 ft: 3\n").await);
+        assert_eq!(
+            read(input).await,
+            "#1\tINITED cov: 2 ft: 2\n#2\tNEW cov: 3 ft: 3\n"
+        );
+        let text = "#1\tINITED cov: 2 ft: 2\n";
+        assert_eq!(read(text.into()).await, text);
+        assert_eq!(read(b"#1".to_vec()).await, "#1");
+        assert_eq!(read(Vec::new()).await, "");
+    }
+
+    #[tokio::test]
+    async fn test_restarts_panicked_tasks() {
+        let dir = std::env::temp_dir().join(format!("fuzz-exporter-panic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("fuzz-0.log");
+        std::fs::write(&log, "").unwrap();
+        let args: Arc<Args> = Arc::new(
+            clap::Parser::try_parse_from(["fuzz-exporter".as_ref(), dir.as_os_str()]).unwrap(),
+        );
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let _recorder = metrics::set_default_local_recorder(&recorder);
+
+        let (updates, mut queued) = tokio::sync::mpsc::channel(16);
+        let mut jobs = HashMap::new();
+        let retired = JobStatus::default();
+        rescan_jobs(&args, &args.paths, &mut jobs, &retired, &updates).unwrap();
+        let job = jobs.values_mut().next().unwrap();
+        let status = job.status.clone();
+        job.task.abort();
+        job.task = tokio::spawn(async { panic!("parser bug") });
+        while !job.task.is_finished() {
+            tokio::task::yield_now().await;
+        }
+
+        rescan_jobs(&args, &args.paths, &mut jobs, &retired, &updates).unwrap();
+        let job = jobs.values().next().unwrap();
+        assert!(Arc::ptr_eq(&job.status, &status));
+        assert!(!job.task.is_finished());
+        let panics = snapshotter.snapshot().into_vec().into_iter().find_map(
+            |(key, _, _, value)| match value {
+                DebugValue::Counter(value) if key.key().name() == "task_panics_total" => {
+                    Some(value)
+                }
+                _ => None,
+            },
+        );
+        assert_eq!(panics, Some(1));
+
+        // the tailer starts at the end of the log
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        std::fs::write(&log, "INFO: x\n").unwrap();
+        let update = tokio::time::timeout(Duration::from_secs(5), queued.recv()).await;
+        assert!(Arc::ptr_eq(&update.unwrap().unwrap().job, &status));
+        drop(jobs);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_manifest_names() {
+        let dir =
+            std::env::temp_dir().join(format!("fuzz-exporter-manifest-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("logs")).unwrap();
+        for log in ["fuzz-0.log", "fuzz-1.log", "fuzz-2.log"] {
+            std::fs::write(dir.join("logs").join(log), "").unwrap();
+        }
+        let manifest = dir.join("manifest.toml");
+        let write_manifest = |fuzz_0: &str| {
+            let toml = format!(
+                "[[log]]\npath = \"fuzz-0.log\"\njob = \"{fuzz_0}\"\ntarget = \"parse_header\"\n\n\
+                 [[log]]\npath = \"*/fuzz-2.log\"\njob = \"fuzz-1\"\n"
+            );
+            std::fs::write(&manifest, toml).unwrap();
+        };
+        write_manifest("parse-header-0");
+        let args: Arc<Args> = Arc::new(
+            clap::Parser::try_parse_from([
+                "fuzz-exporter".as_ref(),
+                "--manifest".as_ref(),
+                manifest.as_os_str(),
+                dir.join("logs").as_os_str(),
+            ])
+            .unwrap(),
+        );
+
+        let (updates, _queued) = tokio::sync::mpsc::channel(16);
+        let mut jobs = HashMap::new();
+        let retired = JobStatus::default();
+        let names = |jobs: &HashMap<PathBuf, crate::Job>| {
+            let mut names: Vec<_> = jobs
+                .values()
+                .map(|job| (job.status.name.clone(), job.status.target.get().cloned()))
+                .collect();
+            names.sort();
+            names
+        };
+        rescan_jobs(&args, &args.paths, &mut jobs, &retired, &updates).unwrap();
+        // the name the manifest gives fuzz-2.log is taken by fuzz-1.log
+        assert_eq!(
+            names(&jobs),
+            [
+                ("fuzz-1".to_owned(), None),
+                ("fuzz-2".to_owned(), None),
+                ("parse-header-0".to_owned(), Some("parse_header".to_owned())),
+            ]
+        );
+        let kept = jobs[&dir.join("logs/fuzz-2.log")].status.clone();
+
+        write_manifest("parse-header-a");
+        rescan_jobs(&args, &args.paths, &mut jobs, &retired, &updates).unwrap();
+        assert_eq!(names(&jobs)[2].0, "parse-header-a");
+        assert!(Arc::ptr_eq(
+            &jobs[&dir.join("logs/fuzz-2.log")].status,
+            &kept
+        ));
+
+        std::fs::write(&manifest, "[[log]]\njob = \"a\"\n").unwrap();
+        assert!(rescan_jobs(&args, &args.paths, &mut jobs, &retired, &updates).is_err());
+        assert_eq!(names(&jobs)[2].0, "parse-header-a");
+        drop(jobs);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// The value of the series of `name` for `job`, or the unlabeled one.
+    fn rendered_value(handle: &PrometheusHandle, name: &str, job: Option<&str>) -> Option<f64> {
+        let job = job.map(|job| format!("job=\"{job}\""));
+        handle.render().lines().find_map(|line| {
+            let (series, value) = line.rsplit_once(' ')?;
+            let matches = match (series.split_once('{'), &job) {
+                (Some((metric, labels)), Some(job)) => {
+                    let mut labels = labels.trim_end_matches('}').split(',');
+                    metric == name && labels.any(|label| label == job)
+                }
+                (None, None) => series == name,
+                _ => false,
+            };
+            matches.then(|| value.parse::<f64>().unwrap())
+        })
+    }
+
+    #[tokio::test]
+    async fn test_totals_keep_removed_jobs() {
+        let dir = std::env::temp_dir().join(format!("fuzz-exporter-retire-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for log in ["fuzz-0.log", "fuzz-1.log"] {
+            std::fs::write(dir.join(log), "").unwrap();
+        }
+        let args: Arc<Args> = Arc::new(
+            clap::Parser::try_parse_from(["fuzz-exporter".as_ref(), dir.as_os_str()]).unwrap(),
+        );
+        // rendered, Prometheus counters never go down
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let (updates, _queued) = tokio::sync::mpsc::channel(16);
+        let mut jobs = HashMap::new();
+        let retired = JobStatus::default();
+        rescan_jobs(&args, &args.paths, &mut jobs, &retired, &updates).unwrap();
+
+        let crash = |job: &Arc<JobStatus>| {
+            let line = "==12345==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x6020";
+            for update in line_updates(&args, job, line) {
+                let job = job.clone();
+                apply_update(&args, JobUpdate { job, update });
+            }
+        };
+        let report = |jobs: &HashMap<PathBuf, crate::Job>| {
+            let statuses = job_statuses(jobs);
+            let statuses: Vec<_> = statuses.iter().map(|job| &**job).collect();
+            metrics::with_local_recorder(&recorder, || report_jobs(&statuses, &retired, &args));
+        };
+        let totals = || {
+            let value = |name| rendered_value(&handle, name, None);
+            (
+                value("crash"),
+                value("incidents_total"),
+                value("lines_total"),
+            )
+        };
+        let fuzz_0 = jobs[&dir.join("fuzz-0.log")].status.clone();
+        let fuzz_1 = jobs[&dir.join("fuzz-1.log")].status.clone();
+        crash(&fuzz_0);
+        crash(&fuzz_0);
+        crash(&fuzz_1);
+        report(&jobs);
+        assert_eq!(totals(), (Some(3.0), Some(3.0), Some(3.0)));
+
+        std::fs::remove_file(dir.join("fuzz-0.log")).unwrap();
+        rescan_jobs(&args, &args.paths, &mut jobs, &retired, &updates).unwrap();
+        assert_eq!(jobs.len(), 1);
+        report(&jobs);
+        assert_eq!(totals(), (Some(3.0), Some(3.0), Some(3.0)));
+        crash(&fuzz_1);
+        report(&jobs);
+        assert_eq!(totals(), (Some(4.0), Some(4.0), Some(4.0)));
+        drop(jobs);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_exec_s_instant() {
+        let job = JobStatus::default();
+        let rate = |total_execs, secs_ago: u64| {
+            job.total_execs_at_ms
+                .store(unix_millis() - secs_ago * 1000, Ordering::Relaxed);
+            job.update_exec_s_instant(total_execs);
+            job.exec_s_instant.load(Ordering::Relaxed)
+        };
+        job.update_exec_s_instant(1000);
+        assert_eq!(rate(5000, 2), 2000);
+        // within a second of the last total
+        assert_eq!(rate(9000, 0), 2000);
+        assert_eq!(rate(u64::MAX / 10, 10), u32::MAX);
+        assert_eq!(rate(u64::MAX, 1), u32::MAX);
+    }
+
+    /// The series of a removed job age out of the registry and the job out
+    /// of `REMOVED_JOBS`, while those of the jobs left are kept.
+    #[tokio::test]
+    async fn test_evicts_removed_jobs() {
+        let dir = std::env::temp_dir().join(format!("fuzz-exporter-evict-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("evict-0.log"), "").unwrap();
+        let args: Arc<Args> = Arc::new(
+            clap::Parser::try_parse_from(["fuzz-exporter".as_ref(), dir.as_os_str()]).unwrap(),
+        );
+        let idle = Duration::from_millis(200);
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new()
+            .idle_timeout(MetricKindMask::ALL, Some(idle))
+            .build_recorder();
+        let handle = recorder.handle();
+        let (recorder, series) = idle::KeepFresh::new(recorder);
+        let render = || {
+            without_removed_jobs(&series, "job", |removed| {
+                strip_jobs(&handle.render(), "job", removed)
+            })
+        };
+        let (updates, _queued) = tokio::sync::mpsc::channel(16);
+        let mut jobs = HashMap::new();
+        let retired = JobStatus::default();
+        rescan_jobs(&args, &args.paths, &mut jobs, &retired, &updates).unwrap();
+
+        let stat = |job: &Arc<JobStatus>| {
+            let line = "#1024\tNEW    cov: 175 ft: 350 corp: 25/14Kb exec/s: 512 rss: 30Mb";
+            for update in line_updates(&args, job, line) {
+                let job = job.clone();
+                apply_update(&args, JobUpdate { job, update });
+            }
+        };
+        let report = |jobs: &HashMap<PathBuf, crate::Job>| {
+            let statuses = job_statuses(jobs);
+            let statuses: Vec<_> = statuses.iter().map(|job| &**job).collect();
+            metrics::with_local_recorder(&recorder, || report_jobs(&statuses, &retired, &args));
+        };
+        let is_removed = || {
+            let removed = REMOVED_JOBS.lock().unwrap_or_else(|e| e.into_inner());
+            removed.contains("evict-1")
+        };
+        let evict_0 = jobs[&dir.join("evict-0.log")].status.clone();
+        stat(&evict_0);
+        report(&jobs);
+        // set once, never updated
+        metrics::with_local_recorder(&recorder, || metrics::gauge!("exporter_build_info").set(1));
+        let before = handle.render().lines().count();
+
+        std::fs::write(dir.join("evict-1.log"), "").unwrap();
+        rescan_jobs(&args, &args.paths, &mut jobs, &retired, &updates).unwrap();
+        stat(&jobs[&dir.join("evict-1.log")].status);
+        report(&jobs);
+        let rendered = render();
+        assert!(rendered.lines().count() > before);
+        assert!(rendered.contains("job=\"evict-1\""));
+
+        std::fs::remove_file(dir.join("evict-1.log")).unwrap();
+        rescan_jobs(&args, &args.paths, &mut jobs, &retired, &updates).unwrap();
+        report(&jobs);
+        assert!(is_removed());
+        assert!(!render().contains("evict-1"));
+        assert!(handle.render().contains("evict-1"));
+
+        tokio::time::sleep(idle * 2).await;
+        // a histogram is only kept with new samples
+        stat(&evict_0);
+        report(&jobs);
+        let rendered = render();
+        assert!(rendered.contains("job=\"evict-0\"") && rendered.contains("exporter_build_info"));
+        assert_eq!(rendered.lines().count(), before);
+        assert_eq!(handle.render().lines().count(), before);
+        assert!(!is_removed());
+        drop(jobs);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Replays the fixture logs into tailed files, through the whole job
+    /// pipeline up to the published metrics.
+    #[tokio::test]
+    async fn test_replay_job_logs() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let dir = std::env::temp_dir().join(format!("fuzz-exporter-replay-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let logs = ["fuzz-0.log", "fuzz-1.log"];
+        for log in logs {
+            std::fs::write(dir.join(log), "").unwrap();
+        }
+        let args: Arc<Args> = Arc::new(
+            clap::Parser::try_parse_from(["fuzz-exporter".as_ref(), dir.as_os_str()]).unwrap(),
+        );
+
+        // rendered, as snapshots of a DebuggingRecorder reset the values
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let _recorder = metrics::set_default_local_recorder(&recorder);
+        let value = |name: &str, job: Option<&str>| rendered_value(&handle, name, job);
+
+        let replay = async {
+            // the tailers start at the end of the logs
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            for log in logs {
+                std::fs::copy(fixtures.join(log), dir.join(log)).unwrap();
+            }
+            while value("lines_total", None) != Some(41.0) {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            // one more report, for the rest of the last line's updates
+            tokio::time::sleep(Duration::from_millis(1100)).await;
+        };
+        tokio::select! {
+            res = jobs_parser(&args, &args.paths) => panic!("stopped: {res:?}"),
+            res = tokio::time::timeout(Duration::from_secs(10), replay) => res.unwrap(),
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let crashed = Some("fuzz-0");
+        assert_eq!(value("cov", crashed), Some(22.0));
+        assert_eq!(value("feat", crashed), Some(30.0));
+        assert_eq!(value("corp", crashed), Some(4.0));
+        assert_eq!(value("exec_s", crashed), Some(682.0));
+        assert_eq!(value("crash", crashed), Some(1.0));
+        assert_eq!(value("new_total", crashed), Some(2.0));
+        assert_eq!(value("new_funcs_total", crashed), Some(1.0));
+        assert_eq!(value("restarts_total", crashed), Some(1.0));
+        assert_eq!(value("artifacts_total", crashed), Some(1.0));
+        // the lines of the crash report and the input after its hex dump are known
+        assert_eq!(value("parse_errors_total", crashed), Some(0.0));
+        assert_eq!(value("job_done", crashed), Some(0.0));
 
-    let jobs = std::iter::repeat_with(JobStatus::default)
-        .take(logs.len())
-        .collect::<Vec<_>>();
-    let jobs = Arc::new(jobs);
+        let done = Some("fuzz-1");
+        assert_eq!(value("cov", done), Some(16.0));
+        assert_eq!(value("exec_s", done), Some(1365.0));
+        assert_eq!(value("rss", done), Some(32.0 * 1024.0 * 1024.0));
+        assert_eq!(value("job_done", done), Some(1.0));
+        assert_eq!(value("parse_errors_total", done), Some(0.0));
 
-    for (idx, log) in logs.iter().enumerate() {
-        let jobs = jobs.clone();
-        let stream = stream_lines(log)?;
+        assert_eq!(value("cov", None), Some(22.0));
+        assert_eq!(value("exec_s", None), Some(2047.0));
+        assert_eq!(value("crash", None), Some(1.0));
+        assert_eq!(value("new_total", None), Some(3.0));
+        assert_eq!(value("job_done", None), Some(1.0));
+        assert_eq!(value("jobs_active", None), Some(2.0));
+    }
 
-        tokio::spawn(async move {
-            let mut stream = tokio::io::BufReader::new(stream).lines();
+    #[test]
+    fn test_sample_rate() {
+        let args: Args =
+            clap::Parser::try_parse_from(["fuzz-exporter", "logs", "--sample-rate", "3"]).unwrap();
+        let job = Arc::new(JobStatus::default());
+        let log = "\
+#2\tINITED cov: 2 ft: 2 corp: 1/1b exec/s: 0 rss: 30Mb
+#3\tNEW    cov: 3 ft: 3 corp: 2/2b exec/s: 0 rss: 30Mb
+#4\tNEW    cov: 4 ft: 4 corp: 3/3b exec/s: 0 rss: 30Mb
+==12345== ERROR: libFuzzer: timeout after 25 seconds
+#5\tNEW    cov: 5 ft: 5 corp: 4/4b exec/s: 0 rss: 30Mb
+#6\tNEW    cov: 6 ft: 6 corp: 5/5b exec/s: 0 rss: 30Mb";
+        for line in log.lines() {
+            let mut updates = line_updates(&args, &job, line);
+            sample(&args, &job, &mut updates);
+            for update in updates {
+                let job = job.clone();
+                apply_update(&args, JobUpdate { job, update });
+            }
+        }
+        // INITED and then every third of the rest
+        assert_eq!(job.cov.load(Ordering::Relaxed), 6);
+        assert_eq!(job.ft.load(Ordering::Relaxed), 6);
+        assert_eq!(job.new_inputs.load(Ordering::Relaxed), 4);
+        assert_eq!(job.timeout.load(Ordering::Relaxed), 1);
+        assert_eq!(job.lines.load(Ordering::Relaxed), 6);
+    }
 
-            while let Ok(Some(line)) = stream.next_line().await {
-                let Ok(parsed) = Parsed::from_log_job(&line) else {
+    #[test]
+    fn test_stale_jobs_left_out_of_aggregates() {
+        let now = unix_secs();
+        let job = |name: &str, cov: u32, last_update: u64| JobStatus {
+            name: name.to_string(),
+            cov: cov.into(),
+            exec_s: 100.into(),
+            lines: 10.into(),
+            added_at: now,
+            last_update: last_update.into(),
+            ..Default::default()
+        };
+        let live = job("live", 10, now);
+        let stale = job("stale", 20, now - 600);
+        let aggregates = |flags: &[&str]| {
+            let recorder = DebuggingRecorder::new();
+            let snapshotter = recorder.snapshotter();
+            metrics::with_local_recorder(&recorder, || {
+                let args = ["fuzz-exporter"].iter().chain(flags);
+                let args: Args = clap::Parser::try_parse_from(args).unwrap();
+                report_jobs(&[&live, &stale], &JobStatus::default(), &args);
+            });
+            let mut values = Vec::new();
+            for (key, _, _, value) in snapshotter.snapshot().into_vec() {
+                let name = key.key().name();
+                if key.key().labels().count() != 0
+                    || !["cov", "exec_s", "exec_s_avg", "jobs_active", "lines_total"]
+                        .contains(&name)
+                {
                     continue;
+                }
+                let value = match value {
+                    DebugValue::Counter(value) => value as f64,
+                    DebugValue::Gauge(value) => value.into_inner(),
+                    DebugValue::Histogram(_) => continue,
+                };
+                values.push((name.to_string(), value));
+            }
+            values.sort_by(|a, b| a.0.cmp(&b.0));
+            values
+        };
+
+        let expected = |cov: f64, exec_s: f64, jobs_active: f64| {
+            vec![
+                ("cov".to_string(), cov),
+                ("exec_s".to_string(), exec_s),
+                ("exec_s_avg".to_string(), 100.0),
+                ("jobs_active".to_string(), jobs_active),
+                ("lines_total".to_string(), 20.0),
+            ]
+        };
+        assert_eq!(aggregates(&[]), expected(20.0, 200.0, 2.0));
+        assert_eq!(
+            aggregates(&["--stale-after", "300"]),
+            expected(10.0, 100.0, 1.0)
+        );
+        let flags = ["--aggregate", "cov=min", "--aggregate", "exec_s=AVG"];
+        assert_eq!(aggregates(&flags), expected(10.0, 100.0, 2.0));
+        let flags = ["fuzz-exporter", "--aggregate", "cov=median"];
+        assert!(<Args as clap::Parser>::try_parse_from(flags).is_err());
+    }
+
+    #[test]
+    fn test_metrics_described() {
+        let args: Args =
+            clap::Parser::try_parse_from(["fuzz-exporter", "--verbose-metrics", "logs"]).unwrap();
+        let job = JobStatus {
+            name: "fuzz-0".to_string(),
+            ..Default::default()
+        };
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            describe_metrics();
+            report_jobs(&[&job], &JobStatus::default(), &args);
+            fork_mode_line(
+                &args,
+                "#1: cov: 1 ft: 1 corp: 1 exec/s 1 oom/timeout/crash: 0/0/0 time: 1s job: 3 dft_time: 2",
+                &mut Default::default(),
+                &[],
+                None,
+            );
+        });
+        let mut fields = Vec::new();
+        for (key, _, description, value) in snapshotter.snapshot().into_vec() {
+            assert!(
+                description.is_some(),
+                "{} isn't described",
+                key.key().name()
+            );
+            if key.key().name() == "time_seconds_total" {
+                assert_eq!(value, DebugValue::Counter(1));
+            }
+            if key.key().name() == "line_field" {
+                let field = key.key().labels().find(|label| label.key() == "field");
+                let DebugValue::Gauge(value) = value else {
+                    panic!("{value:?}");
                 };
-                jobs[idx].update(&parsed);
+                fields.push((field.unwrap().value().to_owned(), value.into_inner()));
             }
+        }
+        assert_eq!(fields.len(), 14);
+        assert!(fields.contains(&("job".to_owned(), 3.0)));
+        assert!(fields.contains(&("dft_time".to_owned(), 2.0)));
+    }
+
+    #[test]
+    fn test_cov_min_skips_new_jobs() {
+        let now = unix_secs();
+        let job = |name: &str, cov: u32, last_update: u64| JobStatus {
+            name: name.to_string(),
+            cov: cov.into(),
+            last_update: last_update.into(),
+            ..Default::default()
+        };
+        let jobs = [job("a", 30, now), job("b", 20, now), job("new", 0, 0)];
+        let args: Args = clap::Parser::try_parse_from(["fuzz-exporter", "logs"]).unwrap();
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            report_jobs(&jobs.each_ref(), &JobStatus::default(), &args);
         });
+        let cov_min = snapshotter.snapshot().into_vec().into_iter().find_map(
+            |(key, _, _, value)| match value {
+                DebugValue::Gauge(value) if key.key().name() == "cov_min" => {
+                    Some(value.into_inner())
+                }
+                _ => None,
+            },
+        );
+        assert_eq!(cov_min, Some(20.0));
     }
 
-    macro_rules! update_metric {
-        ($field:ident, max, $metric:expr) => {{
-            let value = jobs
-                .iter()
-                .map(|job| job.$field.load(Ordering::Acquire))
-                .max()
-                .unwrap_or(0);
-            metrics::gauge!($metric).set(value as f64);
-        }};
-        ($field:ident, sum, $metric:expr) => {{
-            let value: u32 = jobs
-                .iter()
-                .map(|job| job.$field.load(Ordering::Acquire))
-                .sum();
-            metrics::gauge!($metric).set(value as f64);
-        }};
+    #[test]
+    fn test_format_jobs() {
+        let now = unix_secs();
+        let args: Args =
+            clap::Parser::try_parse_from(["fuzz-exporter", "--stale-after", "60", "logs"]).unwrap();
+        let job = |name: &str, lines: u64, last_update: u64| JobStatus {
+            name: name.to_string(),
+            cov: 2163.into(),
+            lines: lines.into(),
+            last_update: last_update.into(),
+            ..Default::default()
+        };
+        let jobs = [
+            job("b", 10, now - 5),
+            job("a", 3, now - 600),
+            job("c", 0, 0),
+        ];
+        let dump = format_jobs(&jobs.each_ref(), &args, now);
+        let rows: Vec<Vec<&str>> = dump
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(rows[0][0], "job");
+        assert_eq!(
+            rows[1],
+            [
+                "a", "-", "2163", "0", "0", "0", "0", "0", "3", "0", "600s", "ago", "stale"
+            ]
+        );
+        assert_eq!(rows[2][..3], ["b", "-", "2163"]);
+        assert_eq!(rows[2][10..], ["5s", "ago", "active"]);
+        assert_eq!(rows[3][10..], ["never", "no", "data"]);
     }
 
-    loop {
-        update_metric!(cov, max, "fuzz_cov");
-        update_metric!(ft, max, "fuzz_feat");
-        update_metric!(corp, max, "fuzz_corp");
-        update_metric!(exec_s, sum, "fuzz_exec_s");
-        update_metric!(corp_size, max, "fuzz_corp_size");
-        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-    }
-}
-fn stream_lines(path: &Path) -> anyhow::Result<ChildStdout> {
-    let command = tokio::process::Command::new("tail")
-        .arg("-f")
-        .arg(path)
-        .stdout(std::process::Stdio::piped())
-        .spawn()
-        .expect("failed to spawn tail");
-    command.stdout.context("failed to get stdout")
-}
-
-async fn journalctl_parser() -> Result<(), anyhow::Error> {
-    println!("Starting journalctl parser");
-
-    let journalctl = tokio::process::Command::new("journalctl")
-        .arg("--user")
-        .arg("-f")
-        .arg("-u")
-        .arg("fuzz")
-        .stdout(std::process::Stdio::piped())
-        .spawn()?;
-    let mut stream = tokio::io::BufReader::new(journalctl.stdout.unwrap()).lines();
-    while let Some(line) = stream.next_line().await? {
-        if let Ok(parsed) = Parsed::from_log(&line) {
-            metrics::gauge!("fuzz_cov").set(parsed.cov as f64);
-            metrics::gauge!("fuzz_feat").set(parsed.ft as f64);
-            metrics::gauge!("fuzz_corp").set(parsed.corp as f64);
-            metrics::gauge!("fuzz_exec_s").set(parsed.exec_s as f64);
-            metrics::gauge!("fuzz_oom").set(parsed.oom as f64);
-            metrics::gauge!("fuzz_timeout").set(parsed.timeout as f64);
-            metrics::gauge!("fuzz_crash").set(parsed.crash as f64);
-            metrics::gauge!("fuzz_time").set(parsed.time as f64);
+    /// The `name` gauges `report_jobs` publishes for `jobs`, by `job` label.
+    fn reported_gauge(jobs: &[&JobStatus], args: &Args, name: &str) -> Vec<(Option<String>, f64)> {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            report_jobs(jobs, &JobStatus::default(), args);
+        });
+        let mut values = Vec::new();
+        for (key, _, _, value) in snapshotter.snapshot().into_vec() {
+            if let DebugValue::Gauge(value) = value
+                && key.key().name() == name
+            {
+                let job = key.key().labels().find(|label| label.key() == "job");
+                values.push((job.map(|job| job.value().to_owned()), value.into_inner()));
+            }
         }
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values
     }
 
-    Ok(())
-}
+    /// Gauges from the ratio of two fields, from the lines of two jobs and one
+    /// without either field, which has no series.
+    #[test]
+    fn test_ratio_gauges() {
+        let args: Args = clap::Parser::try_parse_from(["fuzz-exporter", "logs"]).unwrap();
+        let job = |name: &str, line: &str| {
+            let job = Arc::new(JobStatus {
+                name: name.to_string(),
+                ..Default::default()
+            });
+            for update in line_updates(&args, &job, line) {
+                let job = job.clone();
+                apply_update(&args, JobUpdate { job, update });
+            }
+            job
+        };
+        let none = "INFO: Seed: 1";
+        let fork = |time, dft_time| {
+            format!(
+                "#1: cov: 1 ft: 1 corp: 1 exec/s 1 oom/timeout/crash: 0/0/0 time: {time}s job: 3 dft_time: {dft_time}"
+            )
+        };
+        let rows = [
+            (
+                "ft_per_cov",
+                [
+                    "#1024\tNEW    cov: 10 ft: 30 corp: 3/512b exec/s: 0 rss: 30Mb".to_owned(),
+                    "#1024\tNEW    cov: 10 ft: 10 corp: 3/512b exec/s: 0 rss: 30Mb".to_owned(),
+                ],
+                [2.0, 3.0, 1.0],
+            ),
+            (
+                "corp_avg_input_bytes",
+                [
+                    "#1024\tNEW    cov: 10 ft: 11 corp: 4/4Kb exec/s: 0 rss: 30Mb".to_owned(),
+                    "#1024\tNEW    cov: 10 ft: 11 corp: 10/1000b exec/s: 0 rss: 30Mb".to_owned(),
+                ],
+                [562.0, 1024.0, 100.0],
+            ),
+            (
+                "focus_hit_fraction",
+                [
+                    "#1024\tNEW    cov: 10 ft: 11 corp: 40/4Kb focus: 10 exec/s: 0 rss: 30Mb"
+                        .to_owned(),
+                    "#1024\tNEW    cov: 10 ft: 11 corp: 10/1Kb focus: 5 exec/s: 0 rss: 30Mb"
+                        .to_owned(),
+                ],
+                [0.375, 0.25, 0.5],
+            ),
+            (
+                "cov_per_mexec",
+                [
+                    "#2000000\tREDUCE cov: 300 ft: 11 corp: 3/512b exec/s: 0 rss: 30Mb".to_owned(),
+                    "#500000\tREDUCE cov: 100 ft: 11 corp: 3/512b exec/s: 0 rss: 30Mb".to_owned(),
+                ],
+                [175.0, 150.0, 200.0],
+            ),
+            (
+                "dft_fraction",
+                [fork(120, 30), fork(80, 10)],
+                [0.1875, 0.25, 0.125],
+            ),
+        ];
+        for (gauge, [a, b], [aggregate, a_value, b_value]) in rows.clone() {
+            let jobs = [job("a", &a), job("b", &b), job("new", none)];
+            let statuses: Vec<&JobStatus> = jobs.iter().map(|job| &**job).collect();
+            assert_eq!(
+                reported_gauge(&statuses, &args, gauge),
+                [
+                    (None, aggregate),
+                    (Some("a".to_owned()), a_value),
+                    (Some("b".to_owned()), b_value),
+                ],
+                "{gauge}"
+            );
+            // no aggregate before a job has both fields
+            assert_eq!(reported_gauge(&statuses[2..], &args, gauge), [], "{gauge}");
+        }
 
-// Feb 20 08:24:30 test-server-1 cargo[117394]: #2903021619: cov: 2163 ft: 20854 corp: 2853 exec/s: 1464 oom/timeout/crash: 0/0/0 time: 56383s job: 6125 dft_time: 0
-#[derive(Debug, PartialEq, Eq)]
-struct Parsed {
-    cov: u32,
-    ft: u32,
-    corp: u32,
-    corp_size: u64,
-    exec_s: u32,
-    oom: u32,
-    timeout: u32,
-    crash: u32,
-    time: u32,
-}
+        let args: Args = clap::Parser::try_parse_from([
+            "fuzz-exporter",
+            "--aggregate=cov_per_mexec=max",
+            "logs",
+        ])
+        .unwrap();
+        let (_, [a, b], _) = &rows[3];
+        let jobs = [job("a", a), job("b", b)];
+        let statuses: Vec<&JobStatus> = jobs.iter().map(|job| &**job).collect();
+        assert_eq!(
+            reported_gauge(&statuses, &args, "cov_per_mexec")[0],
+            (None, 200.0)
+        );
+    }
+
+    #[test]
+    fn test_corpus_dir() {
+        let dir = std::env::temp_dir().join(format!("fuzz-exporter-corpus-{}", std::process::id()));
+        for (input, content) in [
+            ("corpora/fuzz-0/a", "abc"),
+            ("corpora/fuzz-0/b", "de"),
+            ("corpora/parse_header/c", "f"),
+            ("shared/d", "ghij"),
+        ] {
+            let input = dir.join(input);
+            std::fs::create_dir_all(input.parent().unwrap()).unwrap();
+            std::fs::write(input, content).unwrap();
+        }
+        std::fs::create_dir_all(dir.join("corpora/fuzz-0/.state")).unwrap();
+        let corpora = format!("--corpus-dir={}", dir.join("corpora").display());
+        let shared = format!("--corpus-dir=fuzz-2={}", dir.join("shared").display());
+        let args: Args =
+            clap::Parser::try_parse_from(["fuzz-exporter", &corpora, &shared, "logs"]).unwrap();
+        assert!(
+            <Args as clap::Parser>::try_parse_from(["fuzz-exporter", "--corpus-dir==x", "logs"])
+                .is_err()
+        );
+
+        let job = |name: &str, target: Option<&str>| {
+            let job = JobStatus {
+                name: name.to_string(),
+                lines: 1.into(),
+                ..Default::default()
+            };
+            if let Some(target) = target {
+                job.target.set(target.to_owned()).unwrap();
+            }
+            Arc::new(job)
+        };
+        let jobs = [
+            job("fuzz-0", None),
+            job("fuzz-1", Some("parse_header")),
+            job("fuzz-2", None),
+            job("fuzz-3", None),
+        ];
+        let (updates, mut queued) = tokio::sync::mpsc::channel(16);
+        scan_corpora(&args, jobs.to_vec(), &updates);
+        while let Ok(update) = queued.try_recv() {
+            apply_update(&args, update);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
 
-impl Parsed {
-    fn from_log(log: &str) -> Result<Parsed, ParseError<&str, ContextError>> {
-        parse_fork_mode.parse(log)
+        let statuses: Vec<&JobStatus> = jobs.iter().map(|job| &**job).collect();
+        let job = |job: &str| Some(job.to_owned());
+        assert_eq!(
+            reported_gauge(&statuses, &args, "corp_bytes"),
+            [
+                (None, 5.0),
+                (job("fuzz-0"), 5.0),
+                (job("fuzz-1"), 1.0),
+                (job("fuzz-2"), 4.0),
+            ]
+        );
+        // per-job series are only republished for changed jobs
+        for job in &jobs {
+            job.dirty.store(true, Ordering::Relaxed);
+        }
+        assert_eq!(
+            reported_gauge(&statuses, &args, "corp_files"),
+            [
+                (None, 2.0),
+                (job("fuzz-0"), 2.0),
+                (job("fuzz-1"), 1.0),
+                (job("fuzz-2"), 1.0),
+            ]
+        );
     }
 
-    fn from_log_job(log: &str) -> Result<Parsed, ParseError<&str, ContextError>> {
-        parse_job_mode.parse(log)
+    #[test]
+    fn test_last_crash_timestamp() {
+        let args: Args = clap::Parser::try_parse_from(["fuzz-exporter", "logs"]).unwrap();
+        let job = |name: &str| {
+            Arc::new(JobStatus {
+                name: name.to_string(),
+                lines: 1.into(),
+                ..Default::default()
+            })
+        };
+        let jobs = [job("fuzz-0"), job("fuzz-1"), job("fuzz-2")];
+        let apply = |job: &Arc<JobStatus>, line: &str| {
+            for update in line_updates(&args, job, line) {
+                let job = job.clone();
+                apply_update(&args, JobUpdate { job, update });
+            }
+        };
+        let before = unix_secs();
+        apply(
+            &jobs[0],
+            "==12345==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011",
+        );
+        let fork = "#2903021619: cov: 2163 ft: 20854 corp: 5491 exec/s: 1410 oom/timeout/crash: 0/0/2 time: 3600s job: 12 dft_time: 0";
+        apply(&jobs[1], fork);
+        assert!(jobs[0].last_crash.load(Ordering::Relaxed) >= before);
+        assert!(jobs[1].last_crash.load(Ordering::Relaxed) >= before);
+        // the same count again isn't a new crash
+        jobs[1].last_crash.store(1, Ordering::Relaxed);
+        apply(&jobs[1], fork);
+        assert_eq!(jobs[1].last_crash.load(Ordering::Relaxed), 1);
+        apply(
+            &jobs[2],
+            "#1024\tNEW    cov: 10 ft: 11 corp: 3/512Kb exec/s: 0 rss: 30Mb",
+        );
+        assert_eq!(jobs[2].last_crash.load(Ordering::Relaxed), 0);
+        jobs[0].last_crash.store(1_700_000_000, Ordering::Relaxed);
+
+        let statuses: Vec<&JobStatus> = jobs.iter().map(|job| &**job).collect();
+        assert_eq!(
+            reported_gauge(&statuses, &args, "last_crash_timestamp"),
+            [
+                (None, 1_700_000_000.0),
+                (Some("fuzz-0".to_owned()), 1_700_000_000.0),
+                (Some("fuzz-1".to_owned()), 1.0),
+            ]
+        );
     }
-}
 
-fn parse_fork_mode(input: &mut &str) -> Result<Parsed> {
-    // 1. Skip everything until "cov:"
-    take_until(0.., "cov:").void().parse_next(input)?;
+    /// Lines keep flowing into the jobs while the registry is scraped, only
+    /// reports touch it.
+    #[test]
+    fn test_updates_with_concurrent_scrapes() {
+        let args: Args = clap::Parser::try_parse_from([
+            "fuzz-exporter",
+            "--exec-s-histogram",
+            "--verbose-metrics",
+            "logs",
+        ])
+        .unwrap();
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let scrapers: Vec<_> = (0..4)
+            .map(|_| {
+                let (handle, done) = (handle.clone(), done.clone());
+                std::thread::spawn(move || {
+                    let mut scrapes = 0;
+                    while !done.load(Ordering::Relaxed) {
+                        handle.render();
+                        scrapes += 1;
+                    }
+                    scrapes
+                })
+            })
+            .collect();
+
+        let jobs: Vec<_> = (0..8)
+            .map(|i| {
+                let log = PathBuf::from(format!("fuzz-{i}.log"));
+                Arc::new(JobStatus::new(format!("fuzz-{i}"), &log, None))
+            })
+            .collect();
+        metrics::with_local_recorder(&recorder, || {
+            for n in 1..=2000 {
+                for job in &jobs {
+                    // parsed already, the parsers trace every step in tests
+                    let parsed = Parsed {
+                        cov: n,
+                        exec_s: 10,
+                        ..Default::default()
+                    };
+                    let updates = [Update::Line, Update::Stats(StatFormat::Job, parsed)];
+                    for update in updates {
+                        let job = job.clone();
+                        apply_update(&args, JobUpdate { job, update });
+                    }
+                }
+                if n % 100 == 0 {
+                    let statuses: Vec<&JobStatus> = jobs.iter().map(|job| &**job).collect();
+                    report_jobs(&statuses, &JobStatus::default(), &args);
+                }
+            }
+        });
+        done.store(true, Ordering::Relaxed);
+        for scraper in scrapers {
+            assert!(scraper.join().unwrap() > 0);
+        }
 
-    let cov = preceded(("cov:", space1), dec_uint).parse_next(input)?;
-    let ft = preceded((space1, ("ft:", space1)), dec_uint).parse_next(input)?;
-    let corp = preceded((space1, ("corp:", space1)), dec_uint).parse_next(input)?;
+        let rendered = handle.render();
+        assert!(rendered.contains("cov{job=\"fuzz-7\"} 2000"), "{rendered}");
+        assert!(
+            rendered.contains("lines_total{job=\"fuzz-0\"} 2000"),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains("exec_s_hist_count{job=\"fuzz-3\"} 2000"),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains("line_field{job=\"fuzz-5\",field=\"cov\"} 2000"),
+            "{rendered}"
+        );
+    }
 
-    // somehow it can both variants of exec/s
-    let exec_s = alt((("exec/s", space1), ("exec/s:", space1)));
-    let exec_s = preceded((space1, exec_s), dec_uint).parse_next(input)?;
+    /// How long a report takes when only some jobs changed, the reason
+    /// per-job series are only republished for those. Not a check, run
+    /// `cargo test --release -- --ignored --nocapture test_report_jobs_timing`.
+    #[test]
+    #[ignore]
+    fn test_report_jobs_timing() {
+        let args: Args = clap::Parser::try_parse_from(["fuzz-exporter", "logs"]).unwrap();
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let jobs: Vec<JobStatus> = (0..500)
+            .map(|i| JobStatus {
+                name: format!("fuzz-{i}"),
+                cov: 2163.into(),
+                lines: 1.into(),
+                ..Default::default()
+            })
+            .collect();
+        let statuses: Vec<&JobStatus> = jobs.iter().collect();
+        let report = |changed: usize| {
+            for job in &jobs[..changed] {
+                job.dirty.store(true, Ordering::Relaxed);
+            }
+            let start = std::time::Instant::now();
+            report_jobs(&statuses, &JobStatus::default(), &args);
+            start.elapsed()
+        };
 
-    // 3. Parse OOM/Timeout/Crash
-    let oom_crash = preceded(
-        (space1, "oom/timeout/crash:", space1),
-        (dec_uint, '/', dec_uint, '/', dec_uint).map(|(o, _, t, _, c)| (o, t, c)),
-    )
-    .parse_next(input)?;
-
-    // 4. Parse time with 's' suffix
-    let time =
-        preceded((space1, ("time:", space1)), terminated(dec_uint, 's')).parse_next(input)?;
-    rest.void().parse_next(input)?;
-
-    Ok(Parsed {
-        cov,
-        ft,
-        corp,
-        corp_size: 0,
-        exec_s,
-        oom: oom_crash.0,
-        timeout: oom_crash.1,
-        crash: oom_crash.2,
-        time,
-    })
-}
+        metrics::with_local_recorder(&recorder, || {
+            // registers every series
+            report(jobs.len());
+            let rounds = 100;
+            let all = (0..rounds).map(|_| report(jobs.len())).sum::<Duration>() / rounds;
+            let some = (0..rounds).map(|_| report(25)).sum::<Duration>() / rounds;
+            eprintln!(
+                "report of {} jobs: {all:?} all changed, {some:?} 25 changed",
+                jobs.len()
+            );
+        });
+    }
 
-//  RELOAD cov: 641 ft: 9191 corp: 1640/591Kb lim: 2411 exec/s: 529 rss: 36Mb
-fn parse_job_mode(input: &mut &str) -> Result<Parsed> {
-    // 1. Skip everything until "cov:"
-    take_until(0.., "cov:").void().parse_next(input)?;
+    #[test]
+    fn test_no_series_for_jobs_without_data() {
+        let args: Args = clap::Parser::try_parse_from(["fuzz-exporter", "logs"]).unwrap();
+        let series = |transient: usize| {
+            let recorder = DebuggingRecorder::new();
+            let snapshotter = recorder.snapshotter();
+            metrics::with_local_recorder(&recorder, || {
+                let job = Arc::new(JobStatus::new(
+                    "fuzz-0".to_owned(),
+                    Path::new("fuzz-0.log"),
+                    None,
+                ));
+                let line = "#1024\tNEW    cov: 10 ft: 11 corp: 3/512Kb exec/s: 0 rss: 30Mb";
+                for update in line_updates(&args, &job, line) {
+                    let job = job.clone();
+                    apply_update(&args, JobUpdate { job, update });
+                }
+                let mut jobs = vec![job];
+                for i in 0..transient {
+                    let name = format!("transient-{i}");
+                    let log = PathBuf::from(format!("{name}.log"));
+                    jobs.push(Arc::new(JobStatus::new(name, &log, None)));
+                }
+                let jobs: Vec<&JobStatus> = jobs.iter().map(|job| &**job).collect();
+                report_jobs(&jobs, &JobStatus::default(), &args);
+                report_jobs(&jobs, &JobStatus::default(), &args);
+            });
+            snapshotter.snapshot().into_vec().len()
+        };
+        assert_eq!(series(200), series(0));
+    }
 
-    let cov = preceded(("cov:", space1), dec_uint).parse_next(input)?;
-    let ft = preceded((space1, "ft:", space1), dec_uint).parse_next(input)?;
+    #[test]
+    fn test_config_file() {
+        let path = std::env::temp_dir().join(format!("fuzz-exporter-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+paths = ["jobs/*.log"]
+interval-secs = 5
+once = true
+aggregate = ["cov=min"]
+metric-prefix = "libfuzzer"
+"#,
+        )
+        .unwrap();
+        let argv = |flags: &[&str]| {
+            let config = ["fuzz-exporter", "--config", path.to_str().unwrap()];
+            config.iter().chain(flags).map(Into::into).collect()
+        };
 
-    // Parse corp: <units>[/<size><unit>]
-    let (corp_units, corp_size) = preceded(
-        (space1, "corp:", space1),
-        (
-            dec_uint,
-            opt(preceded(
-                '/',
-                (
-                    dec_uint,
-                    alt((
-                        "Kb".value(1024u64),
-                        "Mb".value(1024u64 * 1024),
-                        "b".value(1u64),
-                    )),
-                )
-                    .map(|(n, unit): (u64, u64)| n * unit),
-            )),
-        ),
-    )
-    .map(|(units, size)| (units, size.unwrap_or(0)))
-    .parse_next(input)?;
-
-    // Skip remaining fields until exec/s using proper delimiters
-    let _ = terminated(take_until(0.., "exec/s:"), "exec/s:").parse_next(input)?;
-
-    let exec_s = preceded(space1, dec_uint).parse_next(input)?;
-
-    // Skip the rest (rss: XXMb)
-    rest.void().parse_next(input)?;
-
-    Ok(Parsed {
-        cov,
-        ft,
-        corp: corp_units,
-        corp_size,
-        exec_s,
-        oom: 0,
-        timeout: 0,
-        crash: 0,
-        time: 0,
-    })
-}
+        let args = parse_args(argv(&[])).unwrap();
+        assert_eq!(args.paths, [Path::new("jobs/*.log")]);
+        assert_eq!(args.interval_secs, 5);
+        assert!(args.once);
+        assert_eq!(args.aggregate, [("cov".to_owned(), Aggregation::Min)]);
+        assert_eq!(args.metric_prefix, "libfuzzer");
 
-#[cfg(test)]
-mod test {
-    use crate::{Parsed, parse_fork_mode, parse_job_mode};
-    use winnow::Parser;
+        let args = parse_args(argv(&["logs", "--interval-secs", "2"])).unwrap();
+        assert_eq!(args.paths, [Path::new("logs")]);
+        assert_eq!(args.interval_secs, 2);
+        assert_eq!(args.metric_prefix, "libfuzzer");
+
+        std::fs::write(&path, "interval-secs = 5\nintervals = 5\n").unwrap();
+        let err = parse_args(argv(&[])).err().unwrap();
+        assert!(err.to_string().starts_with("unknown key `intervals`"));
+        std::fs::write(&path, "once = \"yes\"\n").unwrap();
+        assert!(parse_args(argv(&["logs"])).is_err());
+        std::fs::write(&path, "interval-secs = 0\n").unwrap();
+        let err = parse_args(argv(&[])).err().unwrap();
+        assert!(err.downcast_ref::<clap::Error>().is_some());
+
+        std::fs::remove_file(&path).unwrap();
+    }
 
     #[test]
-    fn test_parse() {
-        let log = "Feb 20 08:24:30 test-server-1 cargo[117394]: #2903021619: cov: 2163 ft: 20854 corp: 2853 exec/s: 1464 oom/timeout/crash: 0/0/0 time: 56383s job: 6125 dft_time: 0";
-        let parsed = parse_fork_mode.parse(log).unwrap();
+    fn test_global_labels() {
+        let labels = |flags: &[&str]| {
+            let host: &[&str] = if flags.contains(&"--no-host-label") {
+                &[]
+            } else {
+                &["--host-label", "fuzz-1"]
+            };
+            let argv = ["fuzz-exporter"].iter().chain(host);
+            let args: Args = clap::Parser::try_parse_from(argv.chain(flags).chain(&["logs"]))?;
+            let labels = global_labels(&args)?;
+            anyhow::Ok(
+                labels
+                    .iter()
+                    .map(|label| format!("{}={}", label.key(), label.value()))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(labels(&[]).unwrap(), ["host=fuzz-1"]);
         assert_eq!(
-            parsed,
-            Parsed {
-                cov: 2163,
-                ft: 20854,
-                corp: 2853,
-                corp_size: 0,
-                exec_s: 1464,
-                oom: 0,
-                timeout: 0,
-                crash: 0,
-                time: 56383
-            }
-        );
-
-        let log = "Feb 24 16:30:28 test-server-1 cargo[478967]: #190817895: cov: 400 ft: 7911 corp: 1901 exec/s 24015 oom/timeout/crash: 0/0/0 time: 252s job: 110 dft_time: 0";
-        let parsed = parse_fork_mode.parse(log).unwrap();
+            labels(&["--label", "region=eu-west", "--label=campaign=q3=b"]).unwrap(),
+            ["host=fuzz-1", "region=eu-west", "campaign=q3=b"]
+        );
         assert_eq!(
-            parsed,
-            Parsed {
-                cov: 400,
-                ft: 7911,
-                corp: 1901,
-                corp_size: 0,
-                exec_s: 24015,
-                oom: 0,
-                timeout: 0,
-                crash: 0,
-                time: 252
-            }
+            labels(&["--no-host-label", "--label", "host=fuzz-2"]).unwrap(),
+            ["host=fuzz-2"]
         );
+
+        assert!(labels(&["--label", "region"]).is_err());
+        assert!(labels(&["--label", "1region=eu"]).is_err());
+        assert!(labels(&["--label", "re-gion=eu"]).is_err());
+        assert!(labels(&["--label", "__name__=cov"]).is_err());
+        assert!(labels(&["--label", "a=1", "--label", "a=2"]).is_err());
+        assert!(labels(&["--label", "host=fuzz-2"]).is_err());
+        let err = labels(&["--label", "job=fuzz"]).unwrap_err();
+        assert!(err.to_string().contains("exporter's own `job` label"));
     }
 
     #[test]
-    fn test_parse_job_mode() {
-        let log = "RELOAD cov: 641 ft: 9191 corp: 1640/591Kb lim: 2411 exec/s: 529 rss: 36Mb";
-        let parsed = parse_job_mode.parse(log).unwrap();
+    fn test_line_job() {
+        let args: Args = clap::Parser::try_parse_from([
+            "fuzz-exporter",
+            "logs",
+            "--target-prefix-regex",
+            r"^\[([^\]]+)\] ",
+        ])
+        .unwrap();
+        let job = Arc::new(JobStatus {
+            name: "combined".to_string(),
+            ..Default::default()
+        });
+        let (split, line) = line_job(&args, &job, "[http-parse] #12\tpulse  cov: 5");
+        assert_eq!(split.name, "combined[http-parse]");
+        assert_eq!(split.target.get().map(String::as_str), Some("http_parse"));
+        assert_eq!(line, "#12\tpulse  cov: 5");
+        let (again, _) = line_job(&args, &job, "[http-parse] INFO: x");
+        assert!(Arc::ptr_eq(&split, &again));
+
+        let (default, line) = line_job(&args, &job, "INFO: [http-parse] x");
+        assert!(Arc::ptr_eq(&default, &job));
+        assert_eq!(line, "INFO: [http-parse] x");
+        assert_eq!(job.split_jobs().len(), 1);
+
+        let no_group = [
+            "fuzz-exporter",
+            "logs",
+            "--target-prefix-regex",
+            r"^\[\w+\]",
+        ];
+        assert!(<Args as clap::Parser>::try_parse_from(no_group).is_err());
+    }
+
+    #[test]
+    fn test_target_from_path() {
+        let target = |log: &str| target_from_path(Path::new(log));
         assert_eq!(
-            parsed,
-            Parsed {
-                cov: 641,
-                ft: 9191,
-                corp: 1640,
-                corp_size: 591 * 1024,
-                exec_s: 529,
-                oom: 0,
-                timeout: 0,
-                crash: 0,
-                time: 0
-            }
+            target("logs/fuzz_target_1.log").as_deref(),
+            Some("fuzz_target_1")
+        );
+        assert_eq!(
+            target("parse-header.v2.log").as_deref(),
+            Some("parse_header_v2")
+        );
+        assert_eq!(target("fuzz-fuzz-0.log").as_deref(), Some("fuzz_fuzz_0"));
+        assert_eq!(target("logs/fuzz-12.log"), None);
+    }
+
+    #[test]
+    fn test_check_logs_path() {
+        let dir = std::env::temp_dir().join(format!("fuzz-exporter-check-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("fuzz-0.log");
+        std::fs::write(&file, "").unwrap();
+
+        assert!(check_logs_path(&dir).is_ok());
+        assert!(check_logs_path(&dir.join("**/*.log")).is_ok());
+        assert!(check_logs_path(Path::new("*.log")).is_ok());
+
+        let err = check_logs_path(&dir.join("bogus")).unwrap_err().to_string();
+        assert!(
+            err.ends_with(
+                "bogus does not exist, omit the path to read the logs from journalctl instead"
+            ),
+            "{err}"
         );
+        let err = check_logs_path(&dir.join("bogus/*.log"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("bogus does not exist"), "{err}");
+        assert!(check_logs_path(&file).is_ok());
+        let err = check_logs_path(&file.join("*.log"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("fuzz-0.log is not a directory"), "{err}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
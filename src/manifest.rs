@@ -0,0 +1,137 @@
+use anyhow::Context;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Job and target names for logs, from `--manifest`:
+///
+/// ```toml
+/// [[log]]
+/// path = "jobs/fuzz-0.log"
+/// job = "parse-header-0"
+/// target = "parse_header"
+///
+/// [[log]]
+/// path = "http-*.log"
+/// target = "http"
+/// ```
+///
+/// The first entry whose `path` matches a log applies. A `path` without a
+/// `/` is matched against the file name, like `--include`, others against
+/// the log path as found under the paths to tail. Both names are optional.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    entries: Vec<(glob::Pattern, Entry)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Entry {
+    pub job: Option<String>,
+    pub target: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    #[serde(default)]
+    log: Vec<FileEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileEntry {
+    path: String,
+    job: Option<String>,
+    target: Option<String>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> anyhow::Result<Manifest> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Manifest::parse(&text).with_context(|| format!("invalid manifest {}", path.display()))
+    }
+
+    fn parse(text: &str) -> anyhow::Result<Manifest> {
+        let file: File = toml::from_str(text)?;
+        let mut entries = Vec::new();
+        for FileEntry { path, job, target } in file.log {
+            let entry = Entry { job, target };
+            let pattern =
+                glob::Pattern::new(&path).with_context(|| format!("invalid path `{path}`"))?;
+            if let Some(job) = &entry.job {
+                anyhow::ensure!(!job.is_empty(), "empty job name for `{path}`");
+            }
+            if let Some(target) = &entry.target {
+                anyhow::ensure!(!target.is_empty(), "empty target name for `{path}`");
+            }
+            entries.push((pattern, entry));
+        }
+        Ok(Manifest { entries })
+    }
+
+    /// The names given for `log`, if any.
+    pub fn entry(&self, log: &Path) -> Option<&Entry> {
+        let name = log.file_name().map(Path::new);
+        self.entries
+            .iter()
+            .find(|(pattern, _)| {
+                if pattern.as_str().contains('/') {
+                    pattern.matches_path(log)
+                } else {
+                    name.is_some_and(|name| pattern.matches_path(name))
+                }
+            })
+            .map(|(_, entry)| entry)
+    }
+
+    /// The job name given for `log`.
+    pub fn job(&self, log: &Path) -> Option<&str> {
+        self.entry(log)?.job.as_deref()
+    }
+
+    /// The target name given for `log`.
+    pub fn target(&self, log: &Path) -> Option<&str> {
+        self.entry(log)?.target.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Manifest;
+    use std::path::Path;
+
+    #[test]
+    fn test_manifest() {
+        let manifest = Manifest::parse(
+            r#"
+[[log]]
+path = "jobs/fuzz-0.log"
+job = "parse-header-0"
+target = "parse_header"
+
+[[log]]
+path = "http-*.log"
+target = "http"
+
+[[log]]
+path = "*.log"
+job = "never-first"
+"#,
+        )
+        .unwrap();
+        let job = |log: &str| manifest.job(Path::new(log));
+        let target = |log: &str| manifest.target(Path::new(log));
+        assert_eq!(job("jobs/fuzz-0.log"), Some("parse-header-0"));
+        assert_eq!(target("jobs/fuzz-0.log"), Some("parse_header"));
+        assert_eq!(job("other/fuzz-0.log"), Some("never-first"));
+        assert_eq!(target("logs/http-2.log"), Some("http"));
+        assert_eq!(job("logs/http-2.log"), None);
+        assert_eq!(job("logs/fuzz-1.txt"), None);
+
+        assert_eq!(Manifest::parse("").unwrap().job(Path::new("a.log")), None);
+        assert!(Manifest::parse("[[log]]\njob = \"a\"\n").is_err());
+        assert!(Manifest::parse("[[log]]\npath = \"a.log\"\nname = \"a\"\n").is_err());
+        assert!(Manifest::parse("[[log]]\npath = \"[a.log\"\n").is_err());
+        assert!(Manifest::parse("[[log]]\npath = \"a.log\"\njob = \"\"\n").is_err());
+    }
+}
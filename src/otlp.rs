@@ -0,0 +1,360 @@
+use metrics::{
+    Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+    SharedString, Unit,
+};
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::{Meter, MeterProvider};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider, Temporality};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Exports to the OTLP/HTTP collector at `endpoint`, like
+/// `http://localhost:4318/v1/metrics`, every `interval`. The provider has to
+/// be shut down to send the last values. Histograms are sent as deltas, so
+/// the ones of a removed job stop with its samples.
+pub fn meter_provider(endpoint: &str, interval: Duration) -> anyhow::Result<SdkMeterProvider> {
+    let exporter = opentelemetry_otlp::MetricExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .with_temporality(Temporality::LowMemory)
+        .build()?;
+    let reader = PeriodicReader::builder(exporter)
+        .with_interval(interval)
+        .build();
+    Ok(SdkMeterProvider::builder().with_reader(reader).build())
+}
+
+/// Job counters are published with `absolute()` and gauges hold the latest
+/// value, which map onto observable instruments: the values are kept here
+/// and read by the instrument callbacks whenever the reader exports, until
+/// an [`Evictor`] drops them.
+pub struct OtlpRecorder {
+    meter: Meter,
+    counters: Arc<Mutex<Observed>>,
+    gauges: Arc<Mutex<Observed>>,
+    histograms: Mutex<HashMap<String, opentelemetry::metrics::Histogram<f64>>>,
+}
+
+/// Every series of an instrument, `f64` bits for gauges.
+type Series = Arc<Mutex<Vec<(Key, Vec<KeyValue>, Arc<AtomicU64>)>>>;
+
+#[derive(Default)]
+struct Observed {
+    /// By metric name, one instrument each.
+    instruments: HashMap<String, Series>,
+    /// `counter!` and `gauge!` register anew on every call.
+    values: HashMap<Key, Arc<AtomicU64>>,
+}
+
+impl OtlpRecorder {
+    pub fn new(provider: &SdkMeterProvider) -> Self {
+        Self {
+            meter: provider.meter(env!("CARGO_PKG_NAME")),
+            counters: Arc::default(),
+            gauges: Arc::default(),
+            histograms: Mutex::default(),
+        }
+    }
+
+    pub fn evictor(&self) -> Evictor {
+        Evictor {
+            counters: self.counters.clone(),
+            gauges: self.gauges.clone(),
+        }
+    }
+
+    /// The value of `key`, the instrument for its name is built by `build` on
+    /// the first one.
+    fn observe(
+        &self,
+        observed: &Mutex<Observed>,
+        key: &Key,
+        build: impl FnOnce(String, Series),
+    ) -> Arc<AtomicU64> {
+        let mut observed = observed.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(value) = observed.values.get(key) {
+            return value.clone();
+        }
+        let series = observed
+            .instruments
+            .entry(key.name().to_owned())
+            .or_insert_with_key(|name| {
+                let series = Series::default();
+                build(name.clone(), series.clone());
+                series
+            })
+            .clone();
+        let value = Arc::new(AtomicU64::new(0));
+        series.lock().unwrap_or_else(|e| e.into_inner()).push((
+            key.clone(),
+            attributes(key),
+            value.clone(),
+        ));
+        observed.values.insert(key.clone(), value.clone());
+        value
+    }
+}
+
+/// Drops series from an [`OtlpRecorder`], they're registered anew if
+/// they're updated again.
+pub struct Evictor {
+    counters: Arc<Mutex<Observed>>,
+    gauges: Arc<Mutex<Observed>>,
+}
+
+impl Evictor {
+    /// Drops the counters and gauges `stale` picks. Histograms are sent as
+    /// deltas, those of a removed job stop with its samples.
+    pub fn evict(&self, stale: impl Fn(&Key) -> bool) {
+        for observed in [&self.counters, &self.gauges] {
+            let mut observed = observed.lock().unwrap_or_else(|e| e.into_inner());
+            observed.values.retain(|key, _| !stale(key));
+            for series in observed.instruments.values() {
+                let mut series = series.lock().unwrap_or_else(|e| e.into_inner());
+                series.retain(|(key, ..)| !stale(key));
+            }
+        }
+    }
+}
+
+fn attributes(key: &Key) -> Vec<KeyValue> {
+    key.labels()
+        .map(|label| KeyValue::new(label.key().to_owned(), label.value().to_owned()))
+        .collect()
+}
+
+impl Recorder for OtlpRecorder {
+    fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        let value = self.observe(&self.counters, key, |name, series| {
+            self.meter
+                .u64_observable_counter(name)
+                .with_callback(move |observer| {
+                    for (_, attributes, value) in
+                        series.lock().unwrap_or_else(|e| e.into_inner()).iter()
+                    {
+                        observer.observe(value.load(Ordering::Relaxed), attributes);
+                    }
+                })
+                .build();
+        });
+        Counter::from_arc(Arc::new(ObservedCounter(value)))
+    }
+
+    fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+        let value = self.observe(&self.gauges, key, |name, series| {
+            self.meter
+                .f64_observable_gauge(name)
+                .with_callback(move |observer| {
+                    for (_, attributes, value) in
+                        series.lock().unwrap_or_else(|e| e.into_inner()).iter()
+                    {
+                        observer.observe(f64::from_bits(value.load(Ordering::Relaxed)), attributes);
+                    }
+                })
+                .build();
+        });
+        Gauge::from_arc(Arc::new(ObservedGauge(value)))
+    }
+
+    fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+        let mut histograms = self.histograms.lock().unwrap_or_else(|e| e.into_inner());
+        let histogram = histograms
+            .entry(key.name().to_owned())
+            .or_insert_with_key(|name| self.meter.f64_histogram(name.clone()).build())
+            .clone();
+        Histogram::from_arc(Arc::new(RecordedHistogram {
+            histogram,
+            attributes: attributes(key),
+        }))
+    }
+}
+
+struct RecordedHistogram {
+    histogram: opentelemetry::metrics::Histogram<f64>,
+    attributes: Vec<KeyValue>,
+}
+
+impl HistogramFn for RecordedHistogram {
+    fn record(&self, value: f64) {
+        self.histogram.record(value, &self.attributes);
+    }
+}
+
+struct ObservedCounter(Arc<AtomicU64>);
+
+impl CounterFn for ObservedCounter {
+    fn increment(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    fn absolute(&self, value: u64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+}
+
+struct ObservedGauge(Arc<AtomicU64>);
+
+impl GaugeFn for ObservedGauge {
+    fn increment(&self, value: f64) {
+        self.update(|current| current + value);
+    }
+
+    fn decrement(&self, value: f64) {
+        self.update(|current| current - value);
+    }
+
+    fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl ObservedGauge {
+    fn update(&self, f: impl Fn(f64) -> f64) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some(f(f64::from_bits(bits)).to_bits())
+            });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::OtlpRecorder;
+    use metrics::Key;
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData};
+    use opentelemetry_sdk::metrics::{
+        InMemoryMetricExporter, InMemoryMetricExporterBuilder, PeriodicReader, SdkMeterProvider,
+        Temporality,
+    };
+
+    /// The exported values by name and `job`, histograms by their sum.
+    fn exported(exporter: &InMemoryMetricExporter) -> Vec<(String, Option<String>, f64)> {
+        fn job<'a>(mut attributes: impl Iterator<Item = &'a KeyValue>) -> Option<String> {
+            attributes
+                .find(|kv| kv.key.as_str() == "job")
+                .map(|kv| kv.value.to_string())
+        }
+        let mut values = Vec::new();
+        let exported = exporter.get_finished_metrics().unwrap();
+        for metric in exported
+            .iter()
+            .flat_map(|resource| resource.scope_metrics())
+            .flat_map(|scope| scope.metrics())
+        {
+            let name = metric.name().to_owned();
+            match metric.data() {
+                AggregatedMetrics::U64(MetricData::Sum(sum)) => {
+                    for point in sum.data_points() {
+                        values.push((name.clone(), job(point.attributes()), point.value() as f64));
+                    }
+                }
+                AggregatedMetrics::F64(MetricData::Gauge(gauge)) => {
+                    for point in gauge.data_points() {
+                        values.push((name.clone(), job(point.attributes()), point.value()));
+                    }
+                }
+                AggregatedMetrics::F64(MetricData::Histogram(histogram)) => {
+                    for point in histogram.data_points() {
+                        values.push((name.clone(), job(point.attributes()), point.sum()));
+                    }
+                }
+                data => panic!("unexpected {data:?}"),
+            }
+        }
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values
+    }
+
+    #[test]
+    fn test_exports_latest_values() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let recorder = OtlpRecorder::new(&provider);
+
+        metrics::with_local_recorder(&recorder, || {
+            metrics::counter!("lines_total", "job" => "fuzz-0").absolute(5);
+            metrics::counter!("lines_total", "job" => "fuzz-0").absolute(8);
+            metrics::gauge!("cov", "job" => "fuzz-0").set(2163.0);
+            metrics::gauge!("cov", "job" => "fuzz-1").set(20.0);
+        });
+        provider.force_flush().unwrap();
+
+        let job = |job: &str| Some(job.to_owned());
+        assert_eq!(
+            exported(&exporter),
+            [
+                ("cov".to_owned(), job("fuzz-0"), 2163.0),
+                ("cov".to_owned(), job("fuzz-1"), 20.0),
+                ("lines_total".to_owned(), job("fuzz-0"), 8.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_evicts_series() {
+        let exporter = InMemoryMetricExporterBuilder::new()
+            .with_temporality(Temporality::LowMemory)
+            .build();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let recorder = OtlpRecorder::new(&provider);
+
+        metrics::with_local_recorder(&recorder, || {
+            metrics::counter!("lines_total", "job" => "fuzz-1").absolute(5);
+            metrics::gauge!("cov", "job" => "fuzz-0").set(2163.0);
+            metrics::gauge!("cov", "job" => "fuzz-1").set(20.0);
+            metrics::histogram!("exec_s", "job" => "fuzz-1").record(100.0);
+        });
+        provider.force_flush().unwrap();
+        let job = |job: &str| Some(job.to_owned());
+        assert_eq!(
+            exported(&exporter),
+            [
+                ("cov".to_owned(), job("fuzz-0"), 2163.0),
+                ("cov".to_owned(), job("fuzz-1"), 20.0),
+                ("exec_s".to_owned(), job("fuzz-1"), 100.0),
+                ("lines_total".to_owned(), job("fuzz-1"), 5.0),
+            ]
+        );
+
+        // fuzz-1 is removed: its values are dropped and its histogram, a
+        // delta, has no new samples.
+        let of_fuzz_1 = |key: &Key| key.labels().any(|label| label.value() == "fuzz-1");
+        recorder.evictor().evict(of_fuzz_1);
+        exporter.reset();
+        provider.force_flush().unwrap();
+        assert_eq!(
+            exported(&exporter),
+            [("cov".to_owned(), job("fuzz-0"), 2163.0)]
+        );
+        assert!(!recorder.gauges.lock().unwrap().values.keys().any(of_fuzz_1));
+
+        // Back with a new value, it's registered anew.
+        metrics::with_local_recorder(&recorder, || {
+            metrics::gauge!("cov", "job" => "fuzz-1").set(30.0);
+        });
+        exporter.reset();
+        provider.force_flush().unwrap();
+        assert_eq!(
+            exported(&exporter),
+            [
+                ("cov".to_owned(), job("fuzz-0"), 2163.0),
+                ("cov".to_owned(), job("fuzz-1"), 30.0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::BTreeSet;
+
+/// `MetricFamily.metric`.
+const METRIC: u64 = 4;
+/// `Metric.label`.
+const LABEL: u64 = 1;
+
+/// Drops the series with a `label`, the job one, in `jobs` from Prometheus
+/// protobuf output, length-delimited `MetricFamily` messages, and the
+/// families left without any, and returns the jobs it dropped series of.
+/// Everything else is copied as is, so output the filter can't read is
+/// returned whole, with all of `jobs`.
+pub fn strip_jobs(
+    rendered: &[u8],
+    label: &str,
+    jobs: &BTreeSet<String>,
+) -> (Vec<u8>, BTreeSet<String>) {
+    if jobs.is_empty() {
+        return (rendered.to_vec(), BTreeSet::new());
+    }
+    let mut found = BTreeSet::new();
+    match strip(rendered, label, jobs, &mut found) {
+        Some(stripped) => (stripped, found),
+        None => (rendered.to_vec(), jobs.clone()),
+    }
+}
+
+fn strip(
+    mut rendered: &[u8],
+    label: &str,
+    jobs: &BTreeSet<String>,
+    found: &mut BTreeSet<String>,
+) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(rendered.len());
+    while !rendered.is_empty() {
+        let len = usize::try_from(varint(&mut rendered)?).ok()?;
+        let (mut family, rest) = rendered.split_at_checked(len)?;
+        rendered = rest;
+
+        let mut kept = Vec::with_capacity(len);
+        let (mut metrics, mut removed) = (0, 0);
+        while !family.is_empty() {
+            let (number, encoded, payload) = field(&mut family)?;
+            if let (METRIC, Some(metric)) = (number, payload) {
+                metrics += 1;
+                if let Some(job) = of_job(metric, label, jobs)? {
+                    found.insert(job.to_owned());
+                    removed += 1;
+                    continue;
+                }
+            }
+            kept.extend_from_slice(encoded);
+        }
+        if metrics == 0 || removed < metrics {
+            put_varint(&mut out, kept.len() as u64);
+            out.extend(kept);
+        }
+    }
+    Some(out)
+}
+
+/// The job in `jobs` a `Metric` has as its `label`, if any.
+fn of_job<'a>(
+    mut metric: &'a [u8],
+    label: &str,
+    jobs: &BTreeSet<String>,
+) -> Option<Option<&'a str>> {
+    while !metric.is_empty() {
+        let (LABEL, _, Some(mut pair)) = field(&mut metric)? else {
+            continue;
+        };
+        let (mut name, mut value) = (None, None);
+        while !pair.is_empty() {
+            match field(&mut pair)? {
+                (1, _, Some(bytes)) => name = Some(bytes),
+                (2, _, Some(bytes)) => value = Some(bytes),
+                _ => {}
+            }
+        }
+        let value = value.and_then(|value| std::str::from_utf8(value).ok());
+        if name == Some(label.as_bytes()) && value.is_some_and(|job| jobs.contains(job)) {
+            return Some(value);
+        }
+    }
+    Some(None)
+}
+
+/// A field's number, its whole encoding, and its payload if it's
+/// length-delimited.
+type Field<'a> = (u64, &'a [u8], Option<&'a [u8]>);
+
+/// The next field of a message.
+fn field<'a>(buf: &mut &'a [u8]) -> Option<Field<'a>> {
+    let start = *buf;
+    let tag = varint(buf)?;
+    let payload = match tag & 7 {
+        0 => {
+            varint(buf)?;
+            None
+        }
+        1 => {
+            *buf = buf.get(8..)?;
+            None
+        }
+        2 => {
+            let len = usize::try_from(varint(buf)?).ok()?;
+            let (payload, rest) = buf.split_at_checked(len)?;
+            *buf = rest;
+            Some(payload)
+        }
+        5 => {
+            *buf = buf.get(4..)?;
+            None
+        }
+        _ => return None,
+    };
+    Some((tag >> 3, &start[..start.len() - buf.len()], payload))
+}
+
+fn varint(buf: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = buf.split_first()?;
+        *buf = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{put_varint, strip_jobs};
+    use std::collections::BTreeSet;
+
+    /// A length-delimited field.
+    fn bytes(number: u64, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        put_varint(&mut out, number << 3 | 2);
+        put_varint(&mut out, payload.len() as u64);
+        out.extend(payload);
+        out
+    }
+
+    /// A gauge `Metric` with `labels`.
+    fn metric(labels: &[(&str, &str)], value: f64) -> Vec<u8> {
+        let mut metric = Vec::new();
+        for (name, value) in labels {
+            let pair = [bytes(1, name.as_bytes()), bytes(2, value.as_bytes())].concat();
+            metric.extend(bytes(1, &pair));
+        }
+        let mut gauge = vec![1 << 3 | 1];
+        gauge.extend(value.to_le_bytes());
+        metric.extend(bytes(2, &gauge));
+        bytes(4, &metric)
+    }
+
+    fn family(name: &str, metrics: &[Vec<u8>]) -> Vec<u8> {
+        let mut family = bytes(1, name.as_bytes());
+        family.extend([3 << 3, 1]);
+        family.extend(metrics.concat());
+        let mut out = Vec::new();
+        put_varint(&mut out, family.len() as u64);
+        out.extend(family);
+        out
+    }
+
+    #[test]
+    fn test_strip_jobs() {
+        let fuzz_0 = metric(&[("host", "vm"), ("job", "fuzz-0")], 2163.0);
+        let fuzz_1 = metric(&[("host", "vm"), ("job", "fuzz-1")], 20.0);
+        let total = metric(&[("host", "vm")], 2.0);
+        let rendered = [
+            family("fuzz_cov", &[fuzz_0.clone(), fuzz_1.clone()]),
+            family("fuzz_jobs", std::slice::from_ref(&total)),
+            family("fuzz_rss", std::slice::from_ref(&fuzz_1)),
+        ]
+        .concat();
+
+        let none = BTreeSet::new();
+        assert_eq!(
+            strip_jobs(&rendered, "job", &none),
+            (rendered.clone(), none)
+        );
+        let jobs = BTreeSet::from(["fuzz-1".to_owned(), "fuzz-2".to_owned()]);
+        let fuzz_1 = BTreeSet::from(["fuzz-1".to_owned()]);
+        assert_eq!(
+            strip_jobs(&rendered, "job", &jobs),
+            (
+                [family("fuzz_cov", &[fuzz_0]), family("fuzz_jobs", &[total])].concat(),
+                fuzz_1
+            )
+        );
+        assert_eq!(
+            strip_jobs(&rendered, "exported_job", &jobs),
+            (rendered, BTreeSet::new())
+        );
+        // Not protobuf, left alone, and the jobs may still be in it.
+        assert_eq!(
+            strip_jobs(b"\xff", "job", &jobs),
+            (b"\xff".to_vec(), jobs.clone())
+        );
+    }
+
+    #[cfg(feature = "native-histograms")]
+    #[test]
+    fn test_strip_rendered_jobs() {
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, || {
+            metrics::gauge!("cov", "job" => "fuzz-0").set(2163.0);
+            metrics::gauge!("cov", "job" => "fuzz-1").set(20.0);
+            metrics::gauge!("rss", "job" => "fuzz-1").set(1.0);
+        });
+
+        let jobs = BTreeSet::from(["fuzz-1".to_owned()]);
+        let (stripped, _) = strip_jobs(&handle.render_protobuf(), "job", &jobs);
+        let contains = |s: &[u8]| stripped.windows(s.len()).any(|w| w == s);
+        assert!(contains(b"cov") && contains(b"fuzz-0"));
+        assert!(!contains(b"rss") && !contains(b"fuzz-1"));
+    }
+}
@@ -0,0 +1,157 @@
+use metrics::{
+    Counter, CounterFn, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// StatsD counters only take increments and `metrics-exporter-statsd` drops
+/// `absolute()`, which the job counters are published with. This wrapper
+/// turns absolute values into increments since the previous one.
+pub struct AbsoluteCounters<R> {
+    inner: R,
+    counters: Mutex<HashMap<Key, Arc<Delta>>>,
+    /// Series without a `job` label are totals over jobs, which never
+    /// start over like the counts of a single fuzzer do.
+    job_totals: bool,
+}
+
+impl<R> AbsoluteCounters<R> {
+    pub fn new(inner: R, job_totals: bool) -> Self {
+        Self {
+            inner,
+            counters: Mutex::default(),
+            job_totals,
+        }
+    }
+}
+
+impl<R: Recorder> Recorder for AbsoluteCounters<R> {
+    fn describe_counter(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_counter(key, unit, description);
+    }
+
+    fn describe_gauge(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_gauge(key, unit, description);
+    }
+
+    fn describe_histogram(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_histogram(key, unit, description);
+    }
+
+    fn register_counter(&self, key: &Key, metadata: &Metadata<'_>) -> Counter {
+        // the last value has to outlive the handle, `counter!` registers anew
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let delta = counters.entry(key.clone()).or_insert_with(|| {
+            let total = self.job_totals && !key.labels().any(|label| label.key() == "job");
+            Arc::new(Delta {
+                inner: self.inner.register_counter(key, metadata),
+                last: AtomicU64::new(0),
+                resets: !total,
+            })
+        });
+        Counter::from_arc(delta.clone())
+    }
+
+    fn register_gauge(&self, key: &Key, metadata: &Metadata<'_>) -> Gauge {
+        self.inner.register_gauge(key, metadata)
+    }
+
+    fn register_histogram(&self, key: &Key, metadata: &Metadata<'_>) -> Histogram {
+        self.inner.register_histogram(key, metadata)
+    }
+}
+
+struct Delta {
+    inner: Counter,
+    last: AtomicU64,
+    /// Whether a lower value means the source was reset, e.g. a re-added
+    /// job, rather than a count that's already been sent.
+    resets: bool,
+}
+
+impl CounterFn for Delta {
+    fn increment(&self, value: u64) {
+        self.last.fetch_add(value, Ordering::Relaxed);
+        self.inner.increment(value);
+    }
+
+    fn absolute(&self, value: u64) {
+        let delta = if self.resets {
+            let last = self.last.swap(value, Ordering::Relaxed);
+            value.checked_sub(last).unwrap_or(value)
+        } else {
+            // nothing is sent until a total is past what was sent already
+            let last = self.last.fetch_max(value, Ordering::Relaxed);
+            value.saturating_sub(last)
+        };
+        if delta != 0 {
+            self.inner.increment(delta);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AbsoluteCounters;
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    #[test]
+    fn test_absolute_counters_become_increments() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let port = server.local_addr().unwrap().port();
+        let recorder = metrics_exporter_statsd::StatsdBuilder::from("127.0.0.1", port)
+            .with_buffer_size(0)
+            .build(None)
+            .unwrap();
+        let recv = |n| {
+            let mut buf = [0; 64];
+            let mut packets = Vec::new();
+            for _ in 0..n {
+                let len = server.recv(&mut buf).unwrap();
+                packets.push(String::from_utf8_lossy(&buf[..len]).into_owned());
+            }
+            packets
+        };
+
+        let recorder = AbsoluteCounters::new(recorder, false);
+        metrics::with_local_recorder(&recorder, || {
+            metrics::counter!("lines_total").absolute(5);
+            metrics::counter!("lines_total").absolute(8);
+            metrics::counter!("lines_total").absolute(8);
+            metrics::counter!("lines_total").absolute(2);
+        });
+        assert_eq!(
+            recv(3),
+            ["lines_total:5|c", "lines_total:3|c", "lines_total:2|c"]
+        );
+
+        // a job's series starts over, a total that shrinks doesn't
+        let recorder = metrics_exporter_statsd::StatsdBuilder::from("127.0.0.1", port)
+            .with_buffer_size(0)
+            .build(None)
+            .unwrap();
+        let recorder = AbsoluteCounters::new(recorder, true);
+        metrics::with_local_recorder(&recorder, || {
+            metrics::counter!("crash").absolute(100);
+            metrics::counter!("crash", "job" => "fuzz-0").absolute(5);
+            metrics::counter!("crash").absolute(60);
+            metrics::counter!("crash", "job" => "fuzz-0").absolute(2);
+            metrics::counter!("crash").absolute(70);
+            metrics::counter!("crash").absolute(110);
+        });
+        assert_eq!(
+            recv(4),
+            [
+                "crash:100|c",
+                "crash:5|c|#job:fuzz-0",
+                "crash:2|c|#job:fuzz-0",
+                "crash:10|c"
+            ]
+        );
+    }
+}
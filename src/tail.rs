@@ -0,0 +1,455 @@
+use anyhow::Context;
+use futures::{Stream, StreamExt};
+use std::io::{BufRead, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Follows `path` like `tail --follow=name --retry`, starting at the current
+/// end of the file.
+///
+/// The file is polled for new data. If it shrinks or is replaced by a
+/// different file, as logrotate's `create` mode renames it and creates a new
+/// one, it's reopened and read from the start. Whatever was still written to
+/// the old file before that is read first.
+/// A file that doesn't exist yet is waited for up to `wait` and then read
+/// from the start, the fuzzer may not have created its log yet.
+///
+/// A named pipe is read as the fuzzer's output is written to it instead,
+/// see [`fifo_lines`].
+pub async fn stream_lines(
+    path: &Path,
+    wait: Duration,
+) -> anyhow::Result<impl Stream<Item = String> + use<>> {
+    let started = Instant::now();
+    let mut backoff = POLL_INTERVAL;
+    let mut waited = false;
+    let (mut file, from) = loop {
+        // opening one blocks until there's a writer
+        if is_fifo(path) {
+            return Ok(fifo_lines(path).left_stream());
+        }
+        match std::fs::File::open(path) {
+            Ok(file) if waited => break (file, SeekFrom::Start(0)),
+            Ok(file) => break (file, SeekFrom::End(0)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && started.elapsed() < wait => {
+                if !waited {
+                    tracing::warn!("{} doesn't exist yet, waiting for it", path.display());
+                    waited = true;
+                }
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to open {}", path.display()));
+            }
+        }
+    };
+    let pos = file.seek(from)?;
+    let tailer = Tailer {
+        path: path.to_path_buf(),
+        id: file_id(&file.metadata()?),
+        reader: BufReader::new(File::from_std(file)),
+        pos,
+        buf: Vec::new(),
+        after_cr: false,
+    };
+
+    let lines = futures::stream::unfold(tailer, |mut tailer| async move {
+        let line = tailer.next_line().await;
+        Some((line, tailer))
+    });
+    Ok(lines.right_stream())
+}
+
+/// A FIFO has no size to poll or offset to seek to. It's read on a thread
+/// of its own, which blocks until a writer opens it, reads until that writer
+/// closes it and then waits for the next one.
+fn fifo_lines(path: &Path) -> impl Stream<Item = String> + use<> {
+    let (lines, rx) = tokio::sync::mpsc::channel(1024);
+    let path = path.to_path_buf();
+    std::thread::spawn(move || {
+        while !lines.is_closed() {
+            let file = match std::fs::File::open(&path) {
+                Ok(file) => file,
+                Err(e) => {
+                    tracing::warn!("failed to open {}: {e}", path.display());
+                    std::thread::sleep(MAX_BACKOFF);
+                    continue;
+                }
+            };
+            let reader = std::io::BufReader::new(file);
+            for line in reader.split(b'\n').map_while(|line| line.ok()) {
+                for line in split_cr(&String::from_utf8_lossy(&line)) {
+                    if lines.blocking_send(line.to_owned()).is_err() {
+                        return;
+                    }
+                }
+            }
+            tracing::debug!("the writer of {} closed it", path.display());
+        }
+    });
+
+    futures::stream::unfold(rx, |mut rx| async move {
+        let line = rx.recv().await?;
+        Some((line, rx))
+    })
+}
+
+struct Tailer {
+    path: PathBuf,
+    id: Option<u64>,
+    reader: BufReader<File>,
+    /// Offset of the end of the last complete line.
+    pos: u64,
+    /// Partially written line, kept until its newline arrives.
+    buf: Vec<u8>,
+    /// The last line ended with `\r`, a `\n` right after it ends nothing.
+    after_cr: bool,
+}
+
+impl Tailer {
+    /// libFuzzer rewrites some status lines in place with `\r`, so it ends a
+    /// line as well as `\n` does.
+    async fn next_line(&mut self) -> String {
+        loop {
+            let data = match self.reader.fill_buf().await {
+                Ok(data) if !data.is_empty() => data,
+                _ => {
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    self.reopen_if_rotated().await;
+                    continue;
+                }
+            };
+            let Some(end) = data.iter().position(|&b| b == b'\n' || b == b'\r') else {
+                // partial line, wait for the rest
+                let len = data.len();
+                self.buf.extend_from_slice(data);
+                self.reader.consume(len);
+                self.after_cr = false;
+                continue;
+            };
+            let after_cr = std::mem::replace(&mut self.after_cr, data[end] == b'\r');
+            if after_cr && end == 0 && data[0] == b'\n' {
+                // the rest of a `\r\n`, its line was returned at the `\r`
+                self.reader.consume(1);
+                self.pos += 1;
+                continue;
+            }
+            let consumed = self.buf.len() + end + 1;
+            self.buf.extend_from_slice(&data[..end]);
+            self.reader.consume(end + 1);
+            self.pos += consumed as u64;
+            let line = String::from_utf8_lossy(&self.buf).into_owned();
+            self.buf.clear();
+            return line;
+        }
+    }
+
+    async fn reopen_if_rotated(&mut self) {
+        let Ok(meta) = tokio::fs::metadata(&self.path).await else {
+            // rotated away and not recreated yet
+            return;
+        };
+        let replaced = file_id(&meta) != self.id;
+        // the length of a replacement says nothing about the file being read
+        let truncated = !replaced && meta.len() < self.pos + self.buf.len() as u64;
+        if !replaced && !truncated {
+            return;
+        }
+
+        // the fuzzer may still be writing to the renamed file, it's read to
+        // the end first
+        if replaced
+            && self
+                .reader
+                .fill_buf()
+                .await
+                .is_ok_and(|data| !data.is_empty())
+        {
+            return;
+        }
+        let Ok(file) = File::open(&self.path).await else {
+            return;
+        };
+        self.id = file_id(&meta);
+        self.reader = BufReader::new(file);
+        self.pos = 0;
+        self.buf.clear();
+        self.after_cr = false;
+    }
+}
+
+/// Newest compressed copy logrotate left next to `path`, like
+/// `fuzz-0.log.1.gz` or `fuzz-0.log-20250101.gz`.
+pub fn latest_rotated(path: &Path) -> Option<PathBuf> {
+    let pattern = format!("{}*.gz", glob::Pattern::escape(path.to_str()?));
+    glob::glob(&pattern)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|path| Some((path.metadata().ok()?.modified().ok()?, path)))
+        .max()
+        .map(|(_, path)| path)
+}
+
+/// Reads all lines of a gzip-compressed log.
+pub fn read_gz_lines(path: &Path) -> anyhow::Result<impl Iterator<Item = String> + use<>> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let reader = std::io::BufReader::new(flate2::read::MultiGzDecoder::new(file));
+    Ok(reader
+        .split(b'\n')
+        .map_while(|line| line.ok())
+        .flat_map(|line| {
+            split_cr(&String::from_utf8_lossy(&line))
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        }))
+}
+
+/// The lines in a line split off at `\n`, which may hold status lines
+/// libFuzzer rewrote in place with `\r`.
+pub fn split_cr(line: &str) -> impl Iterator<Item = &str> {
+    line.strip_suffix('\r').unwrap_or(line).split('\r')
+}
+
+#[cfg(unix)]
+fn file_id(meta: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Whether `path` is a named pipe, created with `mkfifo`.
+#[cfg(unix)]
+pub fn is_fifo(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|meta| meta.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+pub fn is_fifo(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::{POLL_INTERVAL, latest_rotated, read_gz_lines, stream_lines};
+    use futures::StreamExt;
+    use std::io::ErrorKind;
+    use std::io::Write;
+    use std::time::{Duration, SystemTime};
+
+    #[tokio::test]
+    async fn test_follows_appends_and_truncation() {
+        let path =
+            std::env::temp_dir().join(format!("fuzz-exporter-tail-{}.log", std::process::id()));
+        std::fs::write(&path, "old line\n").unwrap();
+
+        let stream = stream_lines(&path, Duration::ZERO).await.unwrap();
+        let mut stream = std::pin::pin!(stream);
+        let mut next = async || {
+            tokio::time::timeout(Duration::from_secs(5), stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+        };
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        write!(file, "first\r\nsec").unwrap();
+        assert_eq!(next().await, "first");
+        writeln!(file, "ond").unwrap();
+        assert_eq!(next().await, "second");
+
+        write!(file, "#1024\tpulse  cov: 20 ft: 21\r#2048\tpulse  cov: 22").unwrap();
+        assert_eq!(next().await, "#1024\tpulse  cov: 20 ft: 21");
+        write!(file, " ft: 23\r\ndone\n").unwrap();
+        assert_eq!(next().await, "#2048\tpulse  cov: 22 ft: 23");
+        assert_eq!(next().await, "done");
+
+        std::fs::write(&path, "after truncate\n").unwrap();
+        assert_eq!(next().await, "after truncate");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_splits_cr_without_rereading() {
+        let cases: [(&str, &[&str]); 3] = [
+            ("s1\rs2\n", &["s1", "s2"]),
+            ("a\r\nb\n", &["a", "b"]),
+            ("a\rb\nc\n", &["a", "b", "c"]),
+        ];
+        for (i, (content, lines)) in cases.into_iter().enumerate() {
+            let path = std::env::temp_dir()
+                .join(format!("fuzz-exporter-cr-{}-{i}.log", std::process::id()));
+            std::fs::write(&path, "").unwrap();
+            let stream = stream_lines(&path, Duration::ZERO).await.unwrap();
+            let mut stream = std::pin::pin!(stream);
+            let mut file = std::fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .unwrap();
+
+            write!(file, "{content}").unwrap();
+            for &line in lines {
+                let next = tokio::time::timeout(Duration::from_secs(5), stream.next()).await;
+                assert_eq!(next.unwrap().unwrap(), line, "{content:?}");
+            }
+            // polled again with nothing new, the offset matches the file so
+            // it isn't taken for a truncated one and read again
+            let next = tokio::time::timeout(POLL_INTERVAL * 3, stream.next()).await;
+            assert!(next.is_err(), "{content:?} read again: {next:?}");
+            writeln!(file, "after").unwrap();
+            let next = tokio::time::timeout(Duration::from_secs(5), stream.next()).await;
+            assert_eq!(next.unwrap().unwrap(), "after");
+
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_follows_rename_rotation() {
+        let dir = std::env::temp_dir().join(format!("fuzz-exporter-rename-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("fuzz-0.log");
+        std::fs::write(&path, "").unwrap();
+
+        let stream = stream_lines(&path, Duration::ZERO).await.unwrap();
+        let mut stream = std::pin::pin!(stream);
+        let mut next = async || {
+            tokio::time::timeout(Duration::from_secs(5), stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+        };
+
+        let mut old = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        writeln!(old, "before rotation").unwrap();
+        assert_eq!(next().await, "before rotation");
+
+        // logrotate `create`: the old file is renamed and a new, empty one
+        // takes its place while the tailer waits for data, then the fuzzer
+        // still writes a line to the old one
+        let rotate = async {
+            tokio::time::sleep(POLL_INTERVAL / 5).await;
+            std::fs::rename(&path, dir.join("fuzz-0.log.1")).unwrap();
+            std::fs::write(&path, "").unwrap();
+            writeln!(old, "late line").unwrap();
+        };
+        let (line, ()) = tokio::join!(next(), rotate);
+        assert_eq!(line, "late line");
+        let mut new = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        writeln!(new, "new file").unwrap();
+        assert_eq!(next().await, "new file");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_reads_fifo_across_writers() {
+        let path = std::env::temp_dir().join(format!("fuzz-exporter-fifo-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let status = std::process::Command::new("mkfifo")
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let stream = stream_lines(&path, Duration::ZERO).await.unwrap();
+        let mut stream = std::pin::pin!(stream);
+        let mut next = async || {
+            tokio::time::timeout(Duration::from_secs(5), stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+        };
+
+        let write = |content: &'static str| {
+            let path = path.clone();
+            // opening blocks until the reader has it open too
+            std::thread::spawn(move || std::fs::write(path, content).unwrap())
+        };
+        let writer = write("first\r\nsecond\n");
+        assert_eq!(next().await, "first");
+        assert_eq!(next().await, "second");
+        writer.join().unwrap();
+
+        // the writer went away, the next one is read as well
+        let writer = write("#1024\tpulse  cov: 20\rthird");
+        assert_eq!(next().await, "#1024\tpulse  cov: 20");
+        assert_eq!(next().await, "third");
+        writer.join().unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_missing_file() {
+        let path =
+            std::env::temp_dir().join(format!("fuzz-exporter-wait-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let err = stream_lines(&path, Duration::ZERO).await.err().unwrap();
+        let err = err.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        let create = {
+            let path = path.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                std::fs::write(&path, "written before the first poll\n").unwrap();
+            }
+        };
+        let (stream, ()) = tokio::join!(stream_lines(&path, Duration::from_secs(5)), create);
+        let mut stream = std::pin::pin!(stream.unwrap());
+        let line = tokio::time::timeout(Duration::from_secs(5), stream.next()).await;
+        assert_eq!(line.unwrap().unwrap(), "written before the first poll");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_reads_latest_rotated() {
+        let dir = std::env::temp_dir().join(format!("fuzz-exporter-gz-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("fuzz-0.log");
+        let write_gz = |name: &str, content: &str, age_secs: u64| {
+            let file = std::fs::File::create(dir.join(name)).unwrap();
+            let mut gz = flate2::write::GzEncoder::new(file, flate2::Compression::fast());
+            gz.write_all(content.as_bytes()).unwrap();
+            let file = gz.finish().unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(age_secs))
+                .unwrap();
+        };
+        write_gz("fuzz-0.log.2.gz", "older\n", 20);
+        write_gz("fuzz-0.log.1.gz", "first\r\nsecond\rlast", 10);
+        write_gz("fuzz-1.log.1.gz", "other job\n", 0);
+
+        let rotated = latest_rotated(&log).unwrap();
+        assert_eq!(rotated, dir.join("fuzz-0.log.1.gz"));
+        let lines: Vec<_> = read_gz_lines(&rotated).unwrap().collect();
+        assert_eq!(lines, ["first", "second", "last"]);
+        assert_eq!(latest_rotated(&dir.join("fuzz-2.log")), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}